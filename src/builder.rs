@@ -0,0 +1,164 @@
+use crate::config::{
+    Background, ColorChoice, Fallback, Format, SepStyleSource, BACKGROUND, CAUSED_BY_SEP, COLOR,
+    CONNECTIVES, ERROR_SEP, EXIT_CODE, EXIT_CODE_ON_NONE, FORMAT, IO_EXIT_CODES, NOTE_SEP, SEP,
+    SEP_STYLE_SOURCE,
+};
+use crate::segment::Segment;
+use ansi_term::Style;
+use anyhow::{bail, Result};
+use std::sync::OnceLock;
+
+/// Entry point to configure how the process terminates in one place.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{Config, ExitOnError};
+///
+/// Config::builder().exit_code(2).install().unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub struct Config;
+
+impl Config {
+    /// Returns a builder with nothing configured.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+/// Builder for termination settings, see [`Config`].
+///
+/// Settings left untouched keep their defaults.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigBuilder {
+    exit_code: Option<i32>,
+    io_exit_codes: Option<bool>,
+}
+
+impl ConfigBuilder {
+    /// Sets [`EXIT_CODE`].
+    pub fn exit_code(mut self, code: i32) -> Self {
+        self.exit_code = Some(code);
+        self
+    }
+
+    /// Sets [`IO_EXIT_CODES`].
+    pub fn io_exit_codes(mut self, enabled: bool) -> Self {
+        self.io_exit_codes = Some(enabled);
+        self
+    }
+
+    /// Installs the configured settings.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the configured settings has already been set, in
+    /// which case none is installed.
+    pub fn install(self) -> Result<()> {
+        check(&EXIT_CODE, self.exit_code.is_some(), "EXIT_CODE")?;
+        check(
+            &IO_EXIT_CODES,
+            self.io_exit_codes.is_some(),
+            "IO_EXIT_CODES",
+        )?;
+        if let Some(code) = self.exit_code {
+            let _ = EXIT_CODE.set(code);
+        }
+        if let Some(enabled) = self.io_exit_codes {
+            let _ = IO_EXIT_CODES.set(enabled);
+        }
+        Ok(())
+    }
+}
+
+fn check<T>(cell: &OnceLock<T>, configured: bool, name: &str) -> Result<()> {
+    if configured && cell.get().is_some() {
+        bail!("`{}` is already set", name);
+    }
+    Ok(())
+}
+
+/// Snapshot of the effective settings, see [`current_config`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedConfig {
+    pub error_label: &'static str,
+    /// Label of the first cause, see [`connectives`](Self::connectives).
+    pub caused_by_label: String,
+    pub note_label: &'static str,
+    pub error_sep: Segment,
+    pub caused_by_sep: Segment,
+    pub note_sep: Segment,
+    pub label_style: Style,
+    pub note_style: Style,
+    /// Labels of the causes by depth, the last one being used for any
+    /// deeper cause, see [`CONNECTIVES`](crate::CONNECTIVES).
+    pub connectives: Vec<Segment<String>>,
+    /// Message reporting an unexpected `None`.
+    pub none_message: &'static str,
+    pub exit_code: i32,
+    pub exit_code_on_none: i32,
+    pub color: ColorChoice,
+    pub format: Format,
+}
+
+/// Returns the settings in effect, falling back to the defaults for those
+/// left unset.
+///
+/// Unlike reporting an error, this does not lock in the defaults, so that
+/// settings can still be set afterwards.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::{current_config, EXIT_CODE};
+///
+/// assert_eq!(current_config().exit_code, 1);
+///
+/// EXIT_CODE.set(2).unwrap();
+///
+/// let config = current_config();
+/// assert_eq!(config.exit_code, 2);
+/// assert_eq!(config.exit_code_on_none, 2);
+/// assert_eq!(config.error_sep.text, ": ");
+/// ```
+pub fn current_config() -> ResolvedConfig {
+    let sep = SEP.get().copied().unwrap_or_else(Fallback::sep);
+    let exit_code = EXIT_CODE.get().copied().unwrap_or(Fallback::EXIT_CODE);
+    let background = BACKGROUND.get().copied().unwrap_or(Fallback::BACKGROUND);
+    let label_style = match background.resolve() {
+        Background::Light => Fallback::LABEL_STYLE_ON_LIGHT,
+        _ => Fallback::LABEL_STYLE_ON_DARK,
+    };
+    let sep_style_source = SEP_STYLE_SOURCE
+        .get()
+        .copied()
+        .unwrap_or(Fallback::SEP_STYLE_SOURCE);
+    let styled = |sep: Segment, label_style| match sep_style_source {
+        SepStyleSource::Own => sep,
+        SepStyleSource::LikeLabel => Segment::new(sep.text, label_style),
+        SepStyleSource::LikeMessage => Segment::plain(sep.text),
+    };
+    let connectives = match CONNECTIVES.get() {
+        Some(connectives) if !connectives.is_empty() => connectives.clone(),
+        _ => vec![Segment::new("caused by".to_string(), label_style)],
+    };
+    let caused_by_sep = CAUSED_BY_SEP.get().copied().unwrap_or(sep);
+    ResolvedConfig {
+        error_label: "error",
+        caused_by_label: connectives[0].text.clone(),
+        note_label: "note",
+        error_sep: styled(ERROR_SEP.get().copied().unwrap_or(sep), label_style),
+        caused_by_sep: styled(caused_by_sep, connectives[0].style),
+        note_sep: styled(NOTE_SEP.get().copied().unwrap_or(sep), Fallback::NOTE_STYLE),
+        label_style,
+        note_style: Fallback::NOTE_STYLE,
+        connectives,
+        none_message: "unexpected None",
+        exit_code,
+        exit_code_on_none: EXIT_CODE_ON_NONE.get().copied().unwrap_or(exit_code),
+        color: COLOR.get().copied().unwrap_or(Fallback::COLOR),
+        format: FORMAT.get().copied().unwrap_or(Fallback::FORMAT),
+    }
+}
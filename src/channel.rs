@@ -0,0 +1,34 @@
+use crate::report::{self, Failure};
+use anyhow::Error;
+use std::sync::mpsc::Receiver;
+
+/// Collects the errors sent over `receiver` until all senders are dropped,
+/// then exits the process reporting every one of them, regardless of
+/// [`REPORT_ALL_ERRORS`](crate::REPORT_ALL_ERRORS).
+///
+/// Returns if no error was received.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use anyhow::anyhow;
+/// use std::sync::mpsc;
+/// use std::thread;
+///
+/// let (sender, receiver) = mpsc::channel();
+/// for id in 0..3 {
+///     let sender = sender.clone();
+///     thread::spawn(move || {
+///         sender.send(anyhow!("worker {} failed", id)).unwrap();
+///     });
+/// }
+/// drop(sender);
+///
+/// eoe::drain_and_exit(receiver);
+/// ```
+pub fn drain_and_exit(receiver: Receiver<Error>) {
+    let errors: Vec<Error> = receiver.iter().collect();
+    if !errors.is_empty() {
+        report::exit_with_all(Failure::Errors(errors));
+    }
+}
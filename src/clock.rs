@@ -0,0 +1,29 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// Marks the start of the program, from which [`ELAPSED`](crate::ELAPSED)
+/// is measured.
+///
+/// Only the first call has an effect. If never called, the start is
+/// marked the first time the elapsed time is needed, so call this early
+/// in `main`.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, ELAPSED};
+///
+/// eoe::mark_start();
+/// ELAPSED.set(true).unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub fn mark_start() {
+    START.get_or_init(Instant::now);
+}
+
+pub(crate) fn elapsed() -> Duration {
+    START.get_or_init(Instant::now).elapsed()
+}
@@ -0,0 +1,1216 @@
+use crate::segment::{Segment, PLAIN};
+#[cfg(not(feature = "theme-mono"))]
+use ansi_term::Colour;
+use ansi_term::Style;
+use anyhow::Error;
+use std::env;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Separator between a label and its message, shared by all labels
+/// without a separator of their own.
+///
+/// Defaults to an unstyled `": "`, or with the `locale` feature, a
+/// full-width `"："` if the locale detected from `LC_ALL`, `LC_MESSAGES`
+/// or `LANG` is Chinese, Japanese or Korean.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{Colour, ExitOnError, Segment, CAUSED_BY_SEP, SEP};
+///
+/// SEP.set(Segment::plain(" | ")).unwrap();
+/// CAUSED_BY_SEP
+///     .set(Segment::new(" → ", Colour::Yellow.normal()))
+///     .unwrap();
+///
+/// "Mm-noom-ba-deh".parse::<i32>().exit_on_error();
+/// ```
+pub static SEP: OnceLock<Segment> = OnceLock::new();
+
+/// Separator following the `error` label.
+///
+/// Falls back to [`SEP`] if unset.
+pub static ERROR_SEP: OnceLock<Segment> = OnceLock::new();
+
+/// Separator following the `caused by` label.
+///
+/// Falls back to [`SEP`] if unset.
+pub static CAUSED_BY_SEP: OnceLock<Segment> = OnceLock::new();
+
+/// Separator following the `note` label.
+///
+/// Falls back to [`SEP`] if unset.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::testing::run_capturing;
+/// use eoe::{ColorChoice, ExitOnError, Segment, COLOR, NOTE_SEP, SHOW_EXIT_CODE};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// NOTE_SEP.set(Segment::plain(" - ")).unwrap();
+/// SHOW_EXIT_CODE.set(true).unwrap();
+///
+/// let (output, _) = run_capturing(|| {
+///     None::<()>.exit_on_error();
+/// });
+/// assert_eq!(output, "error: unexpected None\nnote - exiting with code 1\n");
+/// ```
+pub static NOTE_SEP: OnceLock<Segment> = OnceLock::new();
+
+/// Labels replacing `caused by`, indexed by the depth of the cause, the
+/// last one being used for any deeper cause.
+///
+/// Defaults to `caused by` at every depth.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::{format_error, ColorChoice, Colour, Segment, COLOR, CONNECTIVES};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// CONNECTIVES
+///     .set(vec![
+///         Segment::new("because".to_string(), Colour::Yellow.bold()),
+///         Segment::new("due to".to_string(), Colour::Yellow.normal()),
+///     ])
+///     .unwrap();
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .context("Doo-boo-boom-ba-beh-beh")
+///     .context("Doo-boo-boom-ba-beh")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     format_error(&error),
+///     concat!(
+///         "error: Doo-boo-boom-ba-beh\n",
+///         "because: Doo-boo-boom-ba-beh-beh\n",
+///         "due to: Doom-boom-ba-beh\n",
+///         "due to: Mm-noom-ba-deh",
+///     )
+/// );
+/// ```
+pub static CONNECTIVES: OnceLock<Vec<Segment<String>>> = OnceLock::new();
+
+/// Where separators take their style from.
+///
+/// Defaults to [`SepStyleSource::Own`].
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::anyhow;
+/// use eoe::{
+///     format_error, ColorChoice, Colour, Segment, SepStyleSource, COLOR, SEP, SEP_STYLE_SOURCE,
+/// };
+///
+/// COLOR.set(ColorChoice::Always).unwrap();
+/// SEP.set(Segment::new(": ", Colour::Yellow.normal())).unwrap();
+/// SEP_STYLE_SOURCE.set(SepStyleSource::LikeMessage).unwrap();
+///
+/// let error = anyhow!("Mm-noom-ba-deh");
+///
+/// // Left unstyled like the message, rather than yellow.
+/// assert!(format_error(&error).ends_with("error\x1b[0m: Mm-noom-ba-deh"));
+/// ```
+pub static SEP_STYLE_SOURCE: OnceLock<SepStyleSource> = OnceLock::new();
+
+/// Source of the separator style, see [`SEP_STYLE_SOURCE`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SepStyleSource {
+    /// The style of the separator [`Segment`] itself.
+    Own,
+    /// The style of the preceding label.
+    LikeLabel,
+    /// The style of the following message, i.e. none.
+    LikeMessage,
+}
+
+/// Whether to append the type name of the underlying error to the
+/// `error` line, e.g. `[std::io::Error]`.
+///
+/// Since [`anyhow::Error`] erases the type, this is a best effort: the
+/// chain is searched for a few well-known standard library error types,
+/// and nothing is appended if none matches.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::{format_error, ColorChoice, COLOR, SHOW_ERROR_TYPE};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// SHOW_ERROR_TYPE.set(true).unwrap();
+///
+/// let error = "Mm-noom-ba-deh".parse::<i32>().unwrap_err().into();
+///
+/// assert_eq!(
+///     format_error(&error),
+///     "error: invalid digit found in string [std::num::ParseIntError]"
+/// );
+/// ```
+pub static SHOW_ERROR_TYPE: OnceLock<bool> = OnceLock::new();
+
+/// String prepended to every line, outermost, e.g. to indent the output
+/// under a parent log line. Each line of a multi-line message gets it.
+///
+/// Defaults to `""`.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::{format_error, ColorChoice, COLOR, GLOBAL_PREFIX};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// GLOBAL_PREFIX.set("    ").unwrap();
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh\nDoom-boom-ba-beh"))
+///     .context("Doo-boo-boom-ba-beh-beh")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     format_error(&error),
+///     concat!(
+///         "    error: Doo-boo-boom-ba-beh-beh\n",
+///         "    caused by: Mm-noom-ba-deh\n",
+///         "    Doom-boom-ba-beh",
+///     )
+/// );
+/// ```
+pub static GLOBAL_PREFIX: OnceLock<&'static str> = OnceLock::new();
+
+/// Name prepended to every line, e.g. `mybin: error: ...`.
+///
+/// Usually set at compile time with [`set_crate_prefix!`](crate::set_crate_prefix).
+///
+/// Defaults to no prefix.
+pub static CRATE_PREFIX: OnceLock<&'static str> = OnceLock::new();
+
+/// Function producing the prefix of each line from its kind and depth in
+/// the chain, in place of [`GLOBAL_PREFIX`] and [`CRATE_PREFIX`].
+///
+/// The blank lines of [`CAUSE_SPACING`] get the prefix of the line that
+/// follows them.
+///
+/// Defaults to no function.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::{format_error, ColorChoice, LineKind, CAUSE_SPACING, COLOR, LINE_PREFIX_FN};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// CAUSE_SPACING.set(true).unwrap();
+/// LINE_PREFIX_FN
+///     .set(Box::new(|kind, depth| match kind {
+///         LineKind::Cause => format!("{}{}. ", "  ".repeat(depth), depth),
+///         _ => String::new(),
+///     }))
+///     .ok();
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .context("Doo-boo-boom-ba-beh-beh")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     format_error(&error),
+///     concat!(
+///         "error: Doo-boo-boom-ba-beh-beh\n",
+///         "  1. caused by: Doom-boom-ba-beh\n",
+///         "    2. \n",
+///         "    2. caused by: Mm-noom-ba-deh",
+///     )
+/// );
+/// ```
+#[allow(clippy::type_complexity)]
+pub static LINE_PREFIX_FN: OnceLock<Box<dyn Fn(LineKind, usize) -> String + Send + Sync>> =
+    OnceLock::new();
+
+/// Kind of a line of the report, see [`LINE_PREFIX_FN`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineKind {
+    Error,
+    Cause,
+    Note,
+    /// Header of a group of causes.
+    Group,
+}
+
+/// Tag appended to the `error` label, e.g. `error[db]: ...`.
+///
+/// Defaults to no tag.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::anyhow;
+/// use eoe::{format_error, ColorChoice, COLOR, SUBSYSTEM};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// SUBSYSTEM.set("db").unwrap();
+///
+/// let error = anyhow!("Mm-noom-ba-deh");
+///
+/// assert_eq!(format_error(&error), "error[db]: Mm-noom-ba-deh");
+/// ```
+pub static SUBSYSTEM: OnceLock<&'static str> = OnceLock::new();
+
+/// String inserted between the label and the separator.
+///
+/// Defaults to `""`.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::anyhow;
+/// use eoe::{format_error, ColorChoice, COLOR, POST_SEP, PRE_SEP};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// PRE_SEP.set(" ").unwrap();
+/// POST_SEP.set("  ").unwrap();
+///
+/// let error = anyhow!("Mm-noom-ba-deh");
+///
+/// assert_eq!(format_error(&error), "error :   Mm-noom-ba-deh");
+/// ```
+pub static PRE_SEP: OnceLock<&'static str> = OnceLock::new();
+
+/// String inserted between the separator and the message.
+///
+/// Defaults to `""`.
+pub static POST_SEP: OnceLock<&'static str> = OnceLock::new();
+
+/// Whether to append the time elapsed since [`mark_start`](crate::mark_start)
+/// to the `error` line, e.g. `(after 42.1s)`.
+///
+/// Defaults to `false`.
+pub static ELAPSED: OnceLock<bool> = OnceLock::new();
+
+/// Whether to label causes with their position in the chain and the
+/// total, e.g. `caused by (2/5)`.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::{format_error, ColorChoice, COLOR, SHOW_CAUSE_COUNT};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// SHOW_CAUSE_COUNT.set(true).unwrap();
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .context("Doo-boo-boom-ba-beh-beh")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     format_error(&error),
+///     concat!(
+///         "error: Doo-boo-boom-ba-beh-beh\n",
+///         "caused by (1/2): Doom-boom-ba-beh\n",
+///         "caused by (2/2): Mm-noom-ba-deh",
+///     )
+/// );
+/// ```
+pub static SHOW_CAUSE_COUNT: OnceLock<bool> = OnceLock::new();
+
+/// Whether to insert a blank line between `caused by` lines.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::{format_error, ColorChoice, CAUSE_SPACING, COLOR};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// CAUSE_SPACING.set(true).unwrap();
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .context("Doo-boo-boom-ba-beh-beh")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     format_error(&error),
+///     concat!(
+///         "error: Doo-boo-boom-ba-beh-beh\n",
+///         "caused by: Doom-boom-ba-beh\n",
+///         "\n",
+///         "caused by: Mm-noom-ba-deh",
+///     )
+/// );
+/// ```
+pub static CAUSE_SPACING: OnceLock<bool> = OnceLock::new();
+
+/// Format of the report.
+///
+/// Defaults to [`Format::Human`].
+///
+/// # Examples
+///
+/// ```should_panic
+/// use anyhow::{anyhow, Context};
+/// use eoe::{ExitOnError, Format, FORMAT};
+///
+/// FORMAT.set(Format::JsonLines).unwrap();
+///
+/// // {"level":"error","msg":"Doom-boom-ba-beh","exit_code":1}
+/// // {"level":"caused_by","depth":1,"msg":"Mm-noom-ba-deh"}
+/// Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .exit_on_error();
+/// ```
+pub static FORMAT: OnceLock<Format> = OnceLock::new();
+
+/// Report format, see [`FORMAT`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Labeled lines, colored according to [`COLOR`].
+    Human,
+    /// One uncolored JSON object per line.
+    JsonLines,
+}
+
+/// Arrangement of the report.
+///
+/// Defaults to [`Layout::Plain`].
+///
+/// # Examples
+///
+/// ```should_panic
+/// use anyhow::{anyhow, Context};
+/// use eoe::{ExitOnError, Layout, LAYOUT};
+///
+/// LAYOUT.set(Layout::BoxedHeadline).unwrap();
+///
+/// // ╭─────────────────────────╮
+/// // │ error: Doom-boom-ba-beh │
+/// // ╰─────────────────────────╯
+/// // caused by: Mm-noom-ba-deh
+/// Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .exit_on_error();
+/// ```
+///
+/// Prefixes such as [`GLOBAL_PREFIX`] are kept outside the box:
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::{format_error, ColorChoice, Layout, COLOR, GLOBAL_PREFIX, LAYOUT};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// GLOBAL_PREFIX.set("> ").unwrap();
+/// LAYOUT.set(Layout::BoxedHeadline).unwrap();
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     format_error(&error),
+///     concat!(
+///         "> ╭─────────────────────────╮\n",
+///         "> │ error: Doom-boom-ba-beh │\n",
+///         "> ╰─────────────────────────╯\n",
+///         "> caused by: Mm-noom-ba-deh",
+///     )
+/// );
+/// ```
+pub static LAYOUT: OnceLock<Layout> = OnceLock::new();
+
+/// Report layout, see [`LAYOUT`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// One line after another.
+    Plain,
+    /// The `error` line drawn in a box, with the causes listed plainly
+    /// beneath.
+    ///
+    /// The box is sized by counting characters, so it is only aligned for
+    /// single-line messages of characters one column wide: full-width
+    /// ones, such as the `：` separator of the `locale` feature, or wide
+    /// icons, overflow it.
+    BoxedHeadline,
+}
+
+/// Whether to report every error, rather than only the first, when
+/// exiting on a collection of results.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, REPORT_ALL_ERRORS};
+///
+/// REPORT_ALL_ERRORS.set(true).unwrap();
+///
+/// ["Mm-noom-ba-deh", "Doom-boom-ba-beh"]
+///     .map(str::parse::<i32>)
+///     .exit_on_error();
+/// ```
+pub static REPORT_ALL_ERRORS: OnceLock<bool> = OnceLock::new();
+
+/// Whether to print only the first line of each message in the chain,
+/// appending `…` to those that were truncated.
+///
+/// Suffixes such as the one of [`SHOW_IO_DETAILS`] are kept.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::{format_error, ColorChoice, COLOR, FIRST_LINE_ONLY, SHOW_IO_DETAILS};
+/// use std::io;
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// FIRST_LINE_ONLY.set(true).unwrap();
+/// SHOW_IO_DETAILS.set(true).unwrap();
+///
+/// let error = io::Error::other("Mm-noom-ba-deh\nDoom-boom-ba-beh").into();
+///
+/// assert_eq!(format_error(&error), "error: Mm-noom-ba-deh… (kind: Other)");
+/// ```
+pub static FIRST_LINE_ONLY: OnceLock<bool> = OnceLock::new();
+
+/// Whether to append the [`std::io::ErrorKind`] to the message of any
+/// [`std::io::Error`] in the chain, e.g. `(kind: NotFound)`.
+///
+/// The OS error code, when there is one, is already part of the message.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, SHOW_IO_DETAILS};
+///
+/// SHOW_IO_DETAILS.set(true).unwrap();
+///
+/// std::fs::read("Mm-noom-ba-deh").exit_on_error();
+/// ```
+pub static SHOW_IO_DETAILS: OnceLock<bool> = OnceLock::new();
+
+/// Code to exit the process with.
+///
+/// Defaults to `1`.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, EXIT_CODE};
+///
+/// EXIT_CODE.set(78).unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub static EXIT_CODE: OnceLock<i32> = OnceLock::new();
+
+/// Code to exit the process with when a value is unexpectedly `None`.
+///
+/// Falls back to [`EXIT_CODE`] if unset.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::testing::run_capturing;
+/// use eoe::{ExitOnError, EXIT_CODE_ON_NONE};
+///
+/// EXIT_CODE_ON_NONE.set(3).unwrap();
+///
+/// let (_, code) = run_capturing(|| {
+///     None::<()>.exit_on_error();
+/// });
+/// assert_eq!(code, 3);
+/// ```
+pub static EXIT_CODE_ON_NONE: OnceLock<i32> = OnceLock::new();
+
+/// Whether to derive the exit code from the first [`std::io::Error`] in
+/// the chain, following `sysexits.h`, e.g. `66` for
+/// [`NotFound`](std::io::ErrorKind::NotFound) or `77` for
+/// [`PermissionDenied`](std::io::ErrorKind::PermissionDenied).
+///
+/// Errors without an I/O error in their chain exit with [`EXIT_CODE`]
+/// as usual.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::testing::run_capturing;
+/// use eoe::{ExitOnError, IO_EXIT_CODES};
+///
+/// IO_EXIT_CODES.set(true).unwrap();
+///
+/// let (_, code) = run_capturing(|| {
+///     std::fs::read("Mm-noom-ba-deh").exit_on_error();
+/// });
+/// assert_eq!(code, 66);
+/// ```
+pub static IO_EXIT_CODES: OnceLock<bool> = OnceLock::new();
+
+/// Whether to exit with `1` plus the number of causes of the error,
+/// clamped to `255`, instead of [`EXIT_CODE`]. Takes precedence over
+/// [`IO_EXIT_CODES`].
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::testing::run_capturing;
+/// use eoe::{ExitOnError, EXIT_CODE_FROM_DEPTH};
+///
+/// EXIT_CODE_FROM_DEPTH.set(true).unwrap();
+///
+/// let (_, code) = run_capturing(|| {
+///     Err::<(), _>(anyhow!("Mm-noom-ba-deh")).exit_on_error();
+/// });
+/// assert_eq!(code, 1);
+///
+/// let (_, code) = run_capturing(|| {
+///     Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///         .context("Doom-boom-ba-beh")
+///         .context("Doo-boo-boom-ba-beh-beh")
+///         .exit_on_error();
+/// });
+/// assert_eq!(code, 3);
+///
+/// let (_, code) = run_capturing(|| {
+///     let error = (0..300).fold(anyhow!("Mm-noom-ba-deh"), |error, depth| {
+///         error.context(depth)
+///     });
+///     Err::<(), _>(error).exit_on_error();
+/// });
+/// assert_eq!(code, 255);
+/// ```
+pub static EXIT_CODE_FROM_DEPTH: OnceLock<bool> = OnceLock::new();
+
+/// Whether to print the captured backtrace as a `note: backtrace:` after
+/// the chain, or `note: run with RUST_BACKTRACE=1 for a backtrace` when
+/// none was captured.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use anyhow::anyhow;
+/// use eoe::{ExitOnError, SUGGEST_BACKTRACE};
+///
+/// SUGGEST_BACKTRACE.set(true).unwrap();
+///
+/// Err::<(), _>(anyhow!("Mm-noom-ba-deh")).exit_on_error();
+/// ```
+///
+/// ```
+/// use anyhow::anyhow;
+/// use eoe::{format_error, ColorChoice, COLOR, SUGGEST_BACKTRACE};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// SUGGEST_BACKTRACE.set(true).unwrap();
+///
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// let error = anyhow!("Mm-noom-ba-deh");
+///
+/// assert!(format_error(&error).starts_with("error: Mm-noom-ba-deh\nnote: backtrace:\n"));
+/// ```
+pub static SUGGEST_BACKTRACE: OnceLock<bool> = OnceLock::new();
+
+/// URL to report bugs at, mentioned in a footer after the report, as a
+/// hyperlink when the output is colored.
+///
+/// Defaults to no footer.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, BUG_REPORT_URL};
+///
+/// BUG_REPORT_URL
+///     .set("https://github.com/Lingxuan-Ye/eoe/issues")
+///     .unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+///
+/// ```
+/// use eoe::testing::run_capturing;
+/// use eoe::{ColorChoice, ExitOnError, BUG_REPORT_URL, COLOR};
+///
+/// COLOR.set(ColorChoice::Always).unwrap();
+/// BUG_REPORT_URL.set("https://example.com").unwrap();
+///
+/// let (output, _) = run_capturing(|| {
+///     None::<()>.exit_on_error();
+/// });
+/// assert!(output.ends_with(concat!(
+///     "please report this error at ",
+///     "\x1b]8;;https://example.com\x1b\\https://example.com\x1b]8;;\x1b\\\n",
+/// )));
+/// ```
+pub static BUG_REPORT_URL: OnceLock<&'static str> = OnceLock::new();
+
+/// Whether to print `note: exiting with code <code>` at the end of the
+/// report. Structured output, i.e. [`Format::JsonLines`] and that of
+/// `exit_on_error_slog`, always carries the code as an `exit_code` field
+/// instead.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, SHOW_EXIT_CODE};
+///
+/// SHOW_EXIT_CODE.set(true).unwrap();
+///
+/// "Mm-noom-ba-deh".parse::<i32>().exit_on_error();
+/// ```
+pub static SHOW_EXIT_CODE: OnceLock<bool> = OnceLock::new();
+
+/// Maximum size of the whole report in bytes, beyond which it is cut
+/// short with a `… (output truncated)` marker.
+///
+/// The limit applies to the final output, escape codes included, and the
+/// marker itself does not count towards it. The output is never cut in the
+/// middle of an escape sequence, and any style left open is reset.
+///
+/// Defaults to no limit.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use anyhow::{anyhow, Context};
+/// use eoe::{ExitOnError, MAX_OUTPUT_BYTES};
+///
+/// MAX_OUTPUT_BYTES.set(32).unwrap();
+///
+/// Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .context("Doo-boo-boom-ba-beh-beh")
+///     .exit_on_error();
+/// ```
+///
+/// ```
+/// use anyhow::anyhow;
+/// use eoe::testing::run_capturing;
+/// use eoe::{ColorChoice, ExitOnError, COLOR, MAX_OUTPUT_BYTES};
+///
+/// COLOR.set(ColorChoice::Always).unwrap();
+/// MAX_OUTPUT_BYTES.set(9).unwrap();
+///
+/// let (output, _) = run_capturing(|| {
+///     Err::<(), _>(anyhow!("Mm-noom-ba-deh")).exit_on_error();
+/// });
+/// assert!(output.ends_with("\x1b[0m\n… (output truncated)\n"));
+/// ```
+pub static MAX_OUTPUT_BYTES: OnceLock<usize> = OnceLock::new();
+
+/// Function categorizing the error, the result of which is printed as
+/// `EOE_REASON=<reason>` after the report, for scripts to act upon.
+///
+/// The line is printed as is, without any prefix or style, and only if
+/// the function returns `Some`. For `None`, it receives an
+/// `unexpected None` error. With [`Format::JsonLines`], it is printed as
+/// a `{"level":"reason","msg":<reason>}` object instead.
+///
+/// Defaults to printing nothing.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, REASON_FN};
+///
+/// REASON_FN
+///     .set(Box::new(|error| {
+///         error
+///             .downcast_ref::<std::io::Error>()
+///             .map(|_| "io_error")
+///     }))
+///     .ok();
+///
+/// std::fs::read("Mm-noom-ba-deh").exit_on_error();
+/// ```
+///
+/// ```
+/// use anyhow::anyhow;
+/// use eoe::testing::run_capturing;
+/// use eoe::{ExitOnError, Format, FORMAT, REASON_FN};
+///
+/// FORMAT.set(Format::JsonLines).unwrap();
+/// REASON_FN.set(Box::new(|_| Some("network"))).ok();
+///
+/// let (output, _) = run_capturing(|| {
+///     Err::<(), _>(anyhow!("Mm-noom-ba-deh")).exit_on_error();
+/// });
+/// assert_eq!(
+///     output,
+///     concat!(
+///         r#"{"level":"error","msg":"Mm-noom-ba-deh","exit_code":1}"#,
+///         "\n",
+///         r#"{"level":"reason","msg":"network"}"#,
+///         "\n",
+///     )
+/// );
+/// ```
+#[allow(clippy::type_complexity)]
+pub static REASON_FN: OnceLock<Box<dyn Fn(&Error) -> Option<&'static str> + Send + Sync>> =
+    OnceLock::new();
+
+/// Hook invoked before anything is printed, so that e.g. a TUI app can
+/// leave raw mode or the alternate screen first.
+///
+/// Defaults to no hook.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, TERMINAL_RESTORE_HOOK};
+///
+/// TERMINAL_RESTORE_HOOK
+///     .set(Box::new(|| print!("\x1b[?1049l")))
+///     .ok();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub static TERMINAL_RESTORE_HOOK: OnceLock<Box<dyn Fn() + Send + Sync>> = OnceLock::new();
+
+/// Hook invoked once the report is printed, right before exiting, e.g. to
+/// flush buffered state. Gets `unexpected None` as error for `None`.
+///
+/// The hook runs at most once, and the process still exits if it panics.
+/// It also runs if the report hit a broken pipe, but not under
+/// [`DRY_RUN`]. Usually set with [`set_on_exit`].
+#[allow(clippy::type_complexity)]
+pub static ON_EXIT: OnceLock<Box<dyn Fn(&Error) + Send + Sync>> = OnceLock::new();
+
+/// Sets [`ON_EXIT`].
+///
+/// # Errors
+///
+/// Fails if [`ON_EXIT`] has already been set.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{set_on_exit, ExitOnError};
+///
+/// set_on_exit(|error| eprintln!("flushing telemetry after: {}", error)).unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub fn set_on_exit<F>(hook: F) -> anyhow::Result<()>
+where
+    F: Fn(&Error) + Send + Sync + 'static,
+{
+    ON_EXIT
+        .set(Box::new(hook))
+        .map_err(|_| anyhow::anyhow!("`ON_EXIT` is already set"))
+}
+
+/// How long to wait for the lock on standard error before writing the
+/// report to standard output instead, e.g. if another thread holds it
+/// forever.
+///
+/// Defaults to waiting indefinitely.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, LOCK_TIMEOUT};
+/// use std::time::Duration;
+///
+/// LOCK_TIMEOUT.set(Duration::from_secs(1)).unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub static LOCK_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// Whether to exit without printing the report.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, QUIET};
+///
+/// QUIET.set(true).unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub static QUIET: OnceLock<bool> = OnceLock::new();
+
+/// Whether to unwind with a [`DryRunExit`](crate::DryRunExit) payload
+/// instead of printing the report and exiting, so that a harness can run
+/// code calling into eoe under [`catch_unwind`](std::panic::catch_unwind).
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::{DryRunExit, ExitOnError, DRY_RUN};
+/// use std::panic::catch_unwind;
+///
+/// DRY_RUN.set(true).unwrap();
+///
+/// let payload = catch_unwind(|| None::<()>.exit_on_error()).unwrap_err();
+/// let exit = payload.downcast::<DryRunExit>().unwrap();
+/// assert_eq!(exit.output, "error: unexpected None\n");
+/// assert_eq!(exit.code, 1);
+/// ```
+pub static DRY_RUN: OnceLock<bool> = OnceLock::new();
+
+/// How long to sleep after printing the report and before exiting, so that
+/// asynchronous log shippers get a chance to drain.
+///
+/// Defaults to zero, i.e. no delay.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, EXIT_DELAY};
+/// use std::time::Duration;
+///
+/// EXIT_DELAY.set(Duration::from_millis(100)).unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub static EXIT_DELAY: OnceLock<Duration> = OnceLock::new();
+
+/// Callback receiving the rendered report, colored according to
+/// [`COLOR`], in place of it being written to its output streams.
+///
+/// Defaults to no callback.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, REPORT_SINK};
+///
+/// REPORT_SINK
+///     .set(Box::new(|report| println!("{}", report.trim_end())))
+///     .ok();
+///
+/// None::<()>.exit_on_error();
+/// ```
+#[allow(clippy::type_complexity)]
+pub static REPORT_SINK: OnceLock<Box<dyn Fn(&str) + Send + Sync>> = OnceLock::new();
+
+/// Stream the `error` and `caused by` lines are written to, along with the
+/// [`REASON_FN`] line.
+///
+/// Defaults to [`Stream::Stderr`].
+pub static ERROR_STREAM: OnceLock<Stream> = OnceLock::new();
+
+/// Stream the `note` lines are written to.
+///
+/// Defaults to [`Stream::Stderr`].
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, Stream, NOTE_STREAM, SHOW_EXIT_CODE};
+///
+/// NOTE_STREAM.set(Stream::Stdout).unwrap();
+/// SHOW_EXIT_CODE.set(true).unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub static NOTE_STREAM: OnceLock<Stream> = OnceLock::new();
+
+/// Output stream, see [`ERROR_STREAM`] and [`NOTE_STREAM`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+    Stderr,
+    Stdout,
+}
+
+/// What to do if writing the report fails.
+///
+/// A broken pipe is not subject to this policy: the process then exits
+/// quietly with `141`, like most Unix tools do.
+///
+/// Defaults to [`OnWriteError::Panic`].
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ExitOnError, OnWriteError, ON_WRITE_ERROR};
+///
+/// ON_WRITE_ERROR.set(OnWriteError::Ignore).unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub static ON_WRITE_ERROR: OnceLock<OnWriteError> = OnceLock::new();
+
+/// Policy on write failure, see [`ON_WRITE_ERROR`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnWriteError {
+    Panic,
+    /// Proceeds to exit with the intended code regardless.
+    Ignore,
+    /// Aborts the process.
+    Abort,
+}
+
+/// When to paint the output with colors.
+///
+/// Defaults to [`ColorChoice::Auto`].
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{ColorChoice, ExitOnError, COLOR};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub static COLOR: OnceLock<ColorChoice> = OnceLock::new();
+
+/// Color preference, see [`COLOR`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Paints only if the output stream is a terminal.
+    Auto,
+    Always,
+    Never,
+}
+
+/// Whether to treat the output stream as not being a terminal,
+/// regardless of what detection says.
+///
+/// This is mainly intended for tests that assert on plain output: unlike
+/// [`COLOR`], it applies to everything gated on terminal detection.
+/// Takes precedence over [`FORCE_TTY`].
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::{will_use_color, FORCE_NO_TTY};
+///
+/// FORCE_NO_TTY.set(true).unwrap();
+///
+/// assert!(!will_use_color());
+/// ```
+pub static FORCE_NO_TTY: OnceLock<bool> = OnceLock::new();
+
+/// Whether to treat the output stream as a terminal, regardless of what
+/// detection says.
+///
+/// Like [`FORCE_NO_TTY`], this is mainly intended for tests.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::{will_use_color, FORCE_TTY};
+///
+/// FORCE_TTY.set(true).unwrap();
+///
+/// assert!(will_use_color());
+/// ```
+pub static FORCE_TTY: OnceLock<bool> = OnceLock::new();
+
+/// Hint about the terminal background, used to pick legible default
+/// colors.
+///
+/// Defaults to [`Background::Dark`].
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{Background, ExitOnError, BACKGROUND};
+///
+/// BACKGROUND.set(Background::Auto).unwrap();
+///
+/// None::<()>.exit_on_error();
+/// ```
+pub static BACKGROUND: OnceLock<Background> = OnceLock::new();
+
+/// Terminal background, see [`BACKGROUND`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    Dark,
+    Light,
+    /// Detects the background from the `COLORFGBG` environment variable,
+    /// assuming [`Background::Dark`] if unavailable.
+    Auto,
+}
+
+impl Background {
+    /// Resolves [`Background::Auto`] to either of the other variants.
+    pub(crate) fn resolve(self) -> Self {
+        match self {
+            Self::Auto => match env::var("COLORFGBG") {
+                Ok(value) => match value.rsplit(';').next().map(str::parse::<u8>) {
+                    Some(Ok(7 | 9..=15)) => Self::Light,
+                    _ => Self::Dark,
+                },
+                Err(_) => Self::Dark,
+            },
+            background => background,
+        }
+    }
+}
+
+/// Icons prefixing the labels.
+///
+/// Defaults to [`IconSet::None`].
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::{format_error, ColorChoice, IconSet, COLOR, ICON_SET};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+/// ICON_SET.set(IconSet::Ascii).unwrap();
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     format_error(&error),
+///     "[E] error: Doom-boom-ba-beh\n[C] caused by: Mm-noom-ba-deh"
+/// );
+/// ```
+pub static ICON_SET: OnceLock<IconSet> = OnceLock::new();
+
+/// Icon set, see [`ICON_SET`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IconSet {
+    /// `✖`, `↳` and `ℹ`.
+    Unicode,
+    /// `[E]`, `[C]` and `[N]`, for terminals lacking Unicode.
+    Ascii,
+    None,
+}
+
+pub(crate) struct Fallback;
+
+impl Fallback {
+    pub(crate) const ERROR_STREAM: Stream = Stream::Stderr;
+    pub(crate) const NOTE_STREAM: Stream = Stream::Stderr;
+    pub(crate) const ON_WRITE_ERROR: OnWriteError = OnWriteError::Panic;
+    pub(crate) const COLOR: ColorChoice = ColorChoice::Auto;
+    pub(crate) const FORCE_NO_TTY: bool = false;
+    pub(crate) const FORCE_TTY: bool = false;
+    pub(crate) const BACKGROUND: Background = Background::Dark;
+    pub(crate) const ICON_SET: IconSet = IconSet::None;
+    pub(crate) const GLOBAL_PREFIX: &'static str = "";
+    pub(crate) const SEP: Segment = Segment::plain(": ");
+    pub(crate) const SEP_STYLE_SOURCE: SepStyleSource = SepStyleSource::Own;
+    pub(crate) const SHOW_ERROR_TYPE: bool = false;
+    pub(crate) const PRE_SEP: &'static str = "";
+    pub(crate) const POST_SEP: &'static str = "";
+    pub(crate) const ELAPSED: bool = false;
+    pub(crate) const SHOW_CAUSE_COUNT: bool = false;
+    pub(crate) const CAUSE_SPACING: bool = false;
+    pub(crate) const QUIET: bool = false;
+    pub(crate) const DRY_RUN: bool = false;
+    pub(crate) const EXIT_DELAY: Duration = Duration::ZERO;
+    pub(crate) const FORMAT: Format = Format::Human;
+    pub(crate) const LAYOUT: Layout = Layout::Plain;
+    pub(crate) const REPORT_ALL_ERRORS: bool = false;
+    pub(crate) const FIRST_LINE_ONLY: bool = false;
+    pub(crate) const SHOW_IO_DETAILS: bool = false;
+    pub(crate) const EXIT_CODE: i32 = 1;
+    pub(crate) const IO_EXIT_CODES: bool = false;
+    pub(crate) const EXIT_CODE_FROM_DEPTH: bool = false;
+    pub(crate) const SUGGEST_BACKTRACE: bool = false;
+    pub(crate) const SHOW_EXIT_CODE: bool = false;
+
+    #[cfg(not(feature = "locale"))]
+    pub(crate) fn sep() -> Segment {
+        Self::SEP
+    }
+
+    #[cfg(feature = "locale")]
+    pub(crate) fn sep() -> Segment {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|key| env::var(key).ok())
+            .find(|value| !value.is_empty());
+        match locale.as_deref().and_then(|value| value.get(..2)) {
+            Some("zh" | "ja" | "ko") => Segment::plain("："),
+            _ => Self::SEP,
+        }
+    }
+}
+
+/// The default theme, painting labels red.
+#[cfg(not(any(feature = "theme-mono", feature = "theme-bright")))]
+impl Fallback {
+    pub(crate) const LABEL_STYLE_ON_DARK: Style = Style {
+        foreground: Some(Colour::Red),
+        is_bold: true,
+        ..PLAIN
+    };
+    pub(crate) const LABEL_STYLE_ON_LIGHT: Style = Style {
+        foreground: Some(Colour::Fixed(124)),
+        is_bold: true,
+        ..PLAIN
+    };
+    pub(crate) const NOTE_STYLE: Style = Style {
+        is_bold: true,
+        ..PLAIN
+    };
+}
+
+/// The `theme-mono` theme, relying on boldness alone.
+#[cfg(feature = "theme-mono")]
+impl Fallback {
+    pub(crate) const LABEL_STYLE_ON_DARK: Style = Style {
+        is_bold: true,
+        ..PLAIN
+    };
+    pub(crate) const LABEL_STYLE_ON_LIGHT: Style = Self::LABEL_STYLE_ON_DARK;
+    pub(crate) const NOTE_STYLE: Style = Style {
+        is_bold: true,
+        is_underline: true,
+        ..PLAIN
+    };
+}
+
+/// The `theme-bright` theme, painting labels in high-intensity colors.
+#[cfg(all(feature = "theme-bright", not(feature = "theme-mono")))]
+impl Fallback {
+    pub(crate) const LABEL_STYLE_ON_DARK: Style = Style {
+        foreground: Some(Colour::Fixed(9)),
+        is_bold: true,
+        ..PLAIN
+    };
+    pub(crate) const LABEL_STYLE_ON_LIGHT: Style = Style {
+        foreground: Some(Colour::Fixed(160)),
+        is_bold: true,
+        ..PLAIN
+    };
+    pub(crate) const NOTE_STYLE: Style = Style {
+        foreground: Some(Colour::Fixed(14)),
+        is_bold: true,
+        ..PLAIN
+    };
+}
@@ -0,0 +1,59 @@
+use crate::report;
+use anyhow::Error;
+use std::thread;
+
+/// Guard exiting the process with an error message when dropped, if an
+/// error was stored with [`fail`](ReportGuard::fail) in the meantime.
+///
+/// Nothing is reported if the guard is dropped while panicking.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::ReportGuard;
+///
+/// let mut guard = ReportGuard::new();
+/// if let Err(error) = "Mm-noom-ba-deh".parse::<i32>() {
+///     guard.fail(error);
+/// }
+/// // cleanup...
+/// ```
+#[derive(Debug, Default)]
+pub struct ReportGuard {
+    error: Option<Error>,
+}
+
+impl ReportGuard {
+    /// Returns a guard with no error stored.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `error`, to be reported when the guard is dropped.
+    ///
+    /// Only the first error stored is reported.
+    pub fn fail<E>(&mut self, error: E)
+    where
+        E: Into<Error>,
+    {
+        if self.error.is_none() {
+            self.error = Some(error.into());
+        }
+    }
+
+    /// Returns whether an error has been stored.
+    pub fn is_failed(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+impl Drop for ReportGuard {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            return;
+        }
+        if let Some(error) = self.error.take() {
+            report::exit_with_error(error);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::Error;
+use std::error::Error as StdError;
+use std::sync::{Mutex, PoisonError};
+
+type Lookup = fn(&(dyn StdError + 'static)) -> Option<String>;
+
+static LOOKUPS: Mutex<Vec<Lookup>> = Mutex::new(Vec::new());
+
+/// Errors documented online.
+///
+/// Once registered with [`register_help_url`], the `error` label of such
+/// errors is rendered as an OSC 8 hyperlink to their documentation when
+/// the output is colored.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::{register_help_url, ExitOnError, HasHelpUrl};
+/// use std::fmt;
+///
+/// #[derive(Debug)]
+/// struct Mm;
+///
+/// impl fmt::Display for Mm {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         f.write_str("Mm-noom-ba-deh")
+///     }
+/// }
+///
+/// impl std::error::Error for Mm {}
+///
+/// impl HasHelpUrl for Mm {
+///     fn help_url(&self) -> String {
+///         "https://example.com/errors/mm".to_string()
+///     }
+/// }
+///
+/// register_help_url::<Mm>();
+///
+/// Err::<(), _>(Mm).exit_on_error();
+/// ```
+pub trait HasHelpUrl {
+    fn help_url(&self) -> String;
+}
+
+/// Makes errors of type `E` found in a chain render with their help URL,
+/// see [`HasHelpUrl`].
+pub fn register_help_url<E>()
+where
+    E: HasHelpUrl + StdError + 'static,
+{
+    let lookup: Lookup = |cause| cause.downcast_ref::<E>().map(E::help_url);
+    LOOKUPS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(lookup);
+}
+
+/// Returns the help URL of the first cause in the chain of `error` whose
+/// type has been registered.
+pub(crate) fn help_url(error: &Error) -> Option<String> {
+    let lookups = LOOKUPS.lock().unwrap_or_else(PoisonError::into_inner);
+    if lookups.is_empty() {
+        return None;
+    }
+    error
+        .chain()
+        .find_map(|cause| lookups.iter().find_map(|lookup| lookup(cause)))
+}
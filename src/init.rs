@@ -0,0 +1,72 @@
+use crate::config::{ColorChoice, Format, COLOR, EXIT_CODE, FORMAT, QUIET};
+use std::env;
+use std::str::FromStr;
+
+/// Configures eoe from the environment, to be called once early in `main`.
+///
+/// | Variable        | Setting       | Values                             |
+/// | --------------- | ------------- | ---------------------------------- |
+/// | `EOE_COLOR`     | [`COLOR`]     | `auto`, `always` or `never`        |
+/// | `EOE_EXIT_CODE` | [`EXIT_CODE`] | an integer                         |
+/// | `EOE_FORMAT`    | [`FORMAT`]    | `human` or `json-lines`            |
+/// | `EOE_QUIET`     | [`QUIET`]     | `1`, `true`, `0` or `false`        |
+///
+/// Unset variables and settings already set are left untouched. Invalid
+/// values are ignored, with a warning in debug builds.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::testing::run_capturing;
+/// use eoe::{ExitOnError, EXIT_CODE};
+///
+/// std::env::set_var("EOE_EXIT_CODE", "3");
+/// eoe::init_from_env();
+///
+/// assert_eq!(EXIT_CODE.get(), Some(&3));
+///
+/// let (_, code) = run_capturing(|| {
+///     None::<()>.exit_on_error();
+/// });
+/// assert_eq!(code, 3);
+/// ```
+pub fn init_from_env() {
+    if let Some(color) = var("EOE_COLOR", |value| match value {
+        "auto" => Some(ColorChoice::Auto),
+        "always" => Some(ColorChoice::Always),
+        "never" => Some(ColorChoice::Never),
+        _ => None,
+    }) {
+        let _ = COLOR.set(color);
+    }
+    if let Some(code) = var("EOE_EXIT_CODE", |value| i32::from_str(value).ok()) {
+        let _ = EXIT_CODE.set(code);
+    }
+    if let Some(format) = var("EOE_FORMAT", |value| match value {
+        "human" => Some(Format::Human),
+        "json-lines" => Some(Format::JsonLines),
+        _ => None,
+    }) {
+        let _ = FORMAT.set(format);
+    }
+    if let Some(quiet) = var("EOE_QUIET", |value| match value {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }) {
+        let _ = QUIET.set(quiet);
+    }
+}
+
+/// Reads and parses the variable `key`, if set.
+fn var<T, F>(key: &str, parse: F) -> Option<T>
+where
+    F: FnOnce(&str) -> Option<T>,
+{
+    let value = env::var(key).ok()?;
+    let parsed = parse(value.trim());
+    if parsed.is_none() && cfg!(debug_assertions) {
+        eprintln!("warning: ignoring invalid value {:?} of `{}`", value, key);
+    }
+    parsed
+}
@@ -1,15 +1,64 @@
 //! This crate helps you exit on error with underlying [`anyhow`]
 //! error handling.
 
+pub use ansi_term::{Colour, Style};
+pub use builder::{current_config, Config, ConfigBuilder, ResolvedConfig};
+pub use channel::drain_and_exit;
+pub use clock::mark_start;
+pub use config::*;
+pub use guard::ReportGuard;
+pub use help::{register_help_url, HasHelpUrl};
+pub use init::init_from_env;
+pub use metrics::{last_exit_code, termination_count};
+pub use output::{will_use_color, DryRunExit};
+pub use panic::install_panic_hook;
+#[cfg(feature = "human-panic")]
+pub use panic::install_panic_hook_with_crash_report;
+pub use parse::parse_or_exit;
+pub use render::{
+    format_error, format_lines, render_with_code, to_anyhow, write_error, write_none,
+};
+pub use segment::Segment;
+pub use writer::ReportWriter;
+
 use anyhow::Error;
-use std::process::exit;
+use internal::Sealed;
+use std::fmt::Display;
+use std::path::Path;
+use std::task::Poll;
 
 #[macro_use]
 mod macros;
+mod builder;
+mod channel;
+mod clock;
+mod config;
+mod guard;
+mod help;
+mod init;
+mod metrics;
+#[cfg(feature = "otel")]
+mod otel;
+mod output;
+mod panic;
+mod parse;
+mod render;
+mod report;
+mod segment;
+mod writer;
+
+#[cfg(feature = "human-panic")]
+pub use human_panic;
+
+#[cfg(feature = "testing")]
+pub mod testing;
 
 /// Exits the process with an error message if the result is an error
 /// or the option is `None`.
 ///
+/// This trait is sealed, but can be implemented for types of your own
+/// through [`ReportableError`].
+///
 /// # Examples
 ///
 /// On error:
@@ -31,8 +80,318 @@ mod macros;
 /// #
 /// None::<()>.exit_on_error();
 /// ```
-pub trait ExitOnError<T>: internal::Sealed {
+pub trait ExitOnError<T>: internal::Sealed<T> {
     fn exit_on_error(self) -> T;
+
+    /// Exits the process with an error message on failure, after wrapping
+    /// the error with the context lazily evaluated from `f`.
+    ///
+    /// This mirrors [`anyhow::Context::with_context`], but terminates.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::ExitOnError;
+    ///
+    /// "Mm-noom-ba-deh"
+    ///     .parse::<i32>()
+    ///     .context_or_exit(|| "Doom-boom-ba-beh");
+    /// ```
+    fn context_or_exit<C, F>(self, f: F) -> T
+    where
+        Self: Sized,
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with_context(failure, f()),
+            Ok(value) => value,
+        }
+    }
+
+    /// Same as [`exit_on_error`](ExitOnError::exit_on_error), but exits
+    /// with `code`, taking precedence over [`EXIT_CODE`] and the like.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::ExitOnError;
+    ///
+    /// // EX_CONFIG
+    /// None::<()>.exit_on_error_with_code(78);
+    /// ```
+    fn exit_on_error_with_code(self, code: i32) -> T
+    where
+        Self: Sized,
+    {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with_code(failure, code),
+            Ok(value) => value,
+        }
+    }
+
+    /// Exits the process with an error message on failure, headed by
+    /// `title`, the original error being reported as the first cause.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::ExitOnError;
+    ///
+    /// // error: Failed to start server
+    /// // caused by: invalid digit found in string
+    /// "Mm-noom-ba-deh"
+    ///     .parse::<u16>()
+    ///     .exit_on_error_titled("Failed to start server");
+    /// ```
+    ///
+    /// `None` keeps exiting with [`EXIT_CODE_ON_NONE`]:
+    ///
+    /// ```
+    /// use eoe::testing::run_capturing;
+    /// use eoe::{ExitOnError, EXIT_CODE_ON_NONE};
+    ///
+    /// EXIT_CODE_ON_NONE.set(3).unwrap();
+    ///
+    /// let (output, code) = run_capturing(|| {
+    ///     None::<()>.exit_on_error_titled("Failed to start server");
+    /// });
+    /// assert_eq!(output, "error: Failed to start server\ncaused by: unexpected None\n");
+    /// assert_eq!(code, 3);
+    /// ```
+    fn exit_on_error_titled<D>(self, title: D) -> T
+    where
+        Self: Sized,
+        D: Display + Send + Sync + 'static,
+    {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with_context(failure, title),
+            Ok(value) => value,
+        }
+    }
+
+    /// Same as [`exit_on_error`](ExitOnError::exit_on_error), but renders
+    /// the root cause, i.e. the concrete error, through `Debug` rather than
+    /// `Display`. Context layers are still rendered through `Display`, and
+    /// backtraces are never included.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::ExitOnError;
+    ///
+    /// // error: Os { code: 2, kind: NotFound, message: "No such file or directory" }
+    /// std::fs::read("Mm-noom-ba-deh").exit_on_error_debug();
+    /// ```
+    ///
+    /// ```
+    /// use anyhow::{anyhow, Context};
+    /// use eoe::testing::run_capturing;
+    /// use eoe::ExitOnError;
+    ///
+    /// let (output, code) = run_capturing(|| {
+    ///     Err::<(), _>(anyhow!("root"))
+    ///         .context("mid")
+    ///         .context("top")
+    ///         .exit_on_error_debug();
+    /// });
+    /// assert_eq!(output, "error: top\ncaused by: mid\ncaused by: \"root\"\n");
+    /// assert_eq!(code, 1);
+    /// ```
+    fn exit_on_error_debug(self) -> T
+    where
+        Self: Sized,
+    {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with_debug(failure),
+            Ok(value) => value,
+        }
+    }
+
+    /// Same as [`exit_on_error`](ExitOnError::exit_on_error), but also
+    /// writes the error as JSON to the file at `path` before exiting, for
+    /// supervisors watching it.
+    ///
+    /// Writing the file is best effort: failures are ignored. Under
+    /// [`DRY_RUN`], nothing is written.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::ExitOnError;
+    ///
+    /// // {"message":"invalid digit found in string","causes":[],"exit_code":1}
+    /// "Mm-noom-ba-deh"
+    ///     .parse::<i32>()
+    ///     .exit_on_error_status_file(std::env::temp_dir().join("status.json"));
+    /// ```
+    ///
+    /// ```
+    /// use eoe::{ExitOnError, DRY_RUN};
+    /// use std::panic::catch_unwind;
+    ///
+    /// DRY_RUN.set(true).unwrap();
+    ///
+    /// let path = std::env::temp_dir().join("eoe-dry-run-status.json");
+    /// let _ = std::fs::remove_file(&path);
+    /// let _ = catch_unwind(|| None::<()>.exit_on_error_status_file(path.clone()));
+    /// assert!(!path.exists());
+    /// ```
+    fn exit_on_error_status_file<P>(self, path: P) -> T
+    where
+        Self: Sized,
+        P: AsRef<Path>,
+    {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with_status_file(failure, path.as_ref()),
+            Ok(value) => value,
+        }
+    }
+
+    /// Same as [`exit_on_error`](ExitOnError::exit_on_error), but records
+    /// the termination before exiting, see [`termination_count`] and
+    /// [`last_exit_code`].
+    ///
+    /// This is mostly useful along with a harness that prevents the
+    /// process from actually exiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eoe::testing::run_capturing;
+    /// use eoe::{last_exit_code, termination_count, ExitOnError};
+    ///
+    /// run_capturing(|| {
+    ///     None::<()>.exit_on_error_reporting();
+    /// });
+    /// assert_eq!(termination_count(), 1);
+    /// assert_eq!(last_exit_code(), Some(1));
+    /// ```
+    fn exit_on_error_reporting(self) -> T
+    where
+        Self: Sized,
+    {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with_reporting(failure),
+            Ok(value) => value,
+        }
+    }
+
+    /// Same as [`exit_on_error`](ExitOnError::exit_on_error), but emits
+    /// the error and its causes through `logger` at error level instead
+    /// of printing them.
+    ///
+    /// Messages are plain, without labels or colors except for the
+    /// `caused by: ` prefix of causes. Since the process exits right
+    /// after, make sure the drain does not hold on to records, e.g. by
+    /// using a synchronous one.
+    ///
+    /// Requires the `slog` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::ExitOnError;
+    /// use slog::{o, Discard, Logger};
+    ///
+    /// let logger = Logger::root(Discard, o!());
+    ///
+    /// "Mm-noom-ba-deh".parse::<i32>().exit_on_error_slog(&logger);
+    /// ```
+    #[cfg(feature = "slog")]
+    fn exit_on_error_slog(self, logger: &slog::Logger) -> T
+    where
+        Self: Sized,
+    {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with_slog(failure, logger),
+            Ok(value) => value,
+        }
+    }
+
+    /// Same as [`exit_on_error`](ExitOnError::exit_on_error), but groups
+    /// the causes under headers according to the category `classify`
+    /// assigns to their message.
+    ///
+    /// Groups are ordered by first appearance. Causes assigned an empty
+    /// category are listed last, under `Other`.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use anyhow::{anyhow, Context};
+    /// use eoe::ExitOnError;
+    ///
+    /// Err::<(), _>(anyhow!("connection refused"))
+    ///     .context("invalid response")
+    ///     .context("failed to fetch config")
+    ///     .exit_on_error_grouped(|message| {
+    ///         if message.contains("connection") {
+    ///             "Network"
+    ///         } else if message.contains("invalid") {
+    ///             "Parsing"
+    ///         } else {
+    ///             ""
+    ///         }
+    ///     });
+    /// ```
+    fn exit_on_error_grouped<F>(self, classify: F) -> T
+    where
+        Self: Sized,
+        F: Fn(&str) -> &'static str,
+    {
+        match self.into_outcome() {
+            Err(failure) => report::exit_grouped(failure, classify),
+            Ok(value) => value,
+        }
+    }
+
+    /// Exits the process with an error message on failure, but restarts
+    /// the program with the same arguments instead if `predicate` returns
+    /// `true` for the error.
+    ///
+    /// On Unix, the current process is replaced with a fresh instance via
+    /// `exec`. Elsewhere, the new instance is spawned and waited for, and
+    /// the process exits with its status.
+    ///
+    /// Under [`DRY_RUN`] or while output is captured, the program is not
+    /// restarted; this unwinds as
+    /// [`exit_on_error`](ExitOnError::exit_on_error) would instead.
+    ///
+    /// For `None`, the predicate receives an `unexpected None` error. For
+    /// a collection of results, it receives the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use anyhow::anyhow;
+    /// use eoe::ExitOnError;
+    ///
+    /// Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+    ///     .exit_on_error_then_reexec(|error| error.to_string() == "Doom-boom-ba-beh");
+    /// ```
+    ///
+    /// ```
+    /// use anyhow::anyhow;
+    /// use eoe::testing::run_capturing;
+    /// use eoe::ExitOnError;
+    ///
+    /// let (output, code) = run_capturing(|| {
+    ///     Err::<(), _>(anyhow!("Mm-noom-ba-deh")).exit_on_error_then_reexec(|_| true);
+    /// });
+    /// assert_eq!(output, "error: Mm-noom-ba-deh\n");
+    /// assert_eq!(code, 1);
+    /// ```
+    fn exit_on_error_then_reexec<P>(self, predicate: P) -> T
+    where
+        Self: Sized,
+        P: FnOnce(&Error) -> bool,
+    {
+        match self.into_outcome() {
+            Err(failure) => report::exit_then_reexec(failure, predicate),
+            Ok(value) => value,
+        }
+    }
 }
 
 impl<T, E> ExitOnError<T> for Result<T, E>
@@ -41,6 +400,9 @@ where
 {
     /// Exits the process with an error message if the result is an error.
     ///
+    /// This covers [`std::io::Result`] as well, for which
+    /// [`SHOW_IO_DETAILS`] and [`IO_EXIT_CODES`] may be of interest.
+    ///
     /// # Examples
     ///
     /// ```should_panic
@@ -54,12 +416,92 @@ where
     /// ```
     fn exit_on_error(self) -> T {
         match self {
-            Err(error) => {
-                let error = error.into();
-                error!(error);
-                error.chain().skip(1).for_each(|cause| caused_by!(cause));
-                exit(1);
-            }
+            Err(error) => report::exit_with_error(error.into()),
+            Ok(value) => value,
+        }
+    }
+}
+
+impl<T, E> ExitOnError<Vec<T>> for Vec<Result<T, E>>
+where
+    E: Into<Error>,
+{
+    /// Exits the process with an error message if any of the results is
+    /// an error, otherwise returns the collected values.
+    ///
+    /// Only the first error is reported unless [`REPORT_ALL_ERRORS`] is
+    /// set.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::ExitOnError;
+    ///
+    /// vec!["4", "Mm-noom-ba-deh", "2"]
+    ///     .into_iter()
+    ///     .map(str::parse::<i32>)
+    ///     .collect::<Vec<_>>()
+    ///     .exit_on_error();
+    /// ```
+    fn exit_on_error(self) -> Vec<T> {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with(failure),
+            Ok(values) => values,
+        }
+    }
+}
+
+impl<T, E, const N: usize> ExitOnError<[T; N]> for [Result<T, E>; N]
+where
+    E: Into<Error>,
+{
+    /// Exits the process with an error message if any of the results is
+    /// an error, otherwise returns the values.
+    ///
+    /// Only the first error is reported unless [`REPORT_ALL_ERRORS`] is
+    /// set.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::ExitOnError;
+    ///
+    /// ["4", "Mm-noom-ba-deh", "2"]
+    ///     .map(str::parse::<i32>)
+    ///     .exit_on_error();
+    /// ```
+    fn exit_on_error(self) -> [T; N] {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with(failure),
+            Ok(values) => values,
+        }
+    }
+}
+
+impl<T, E> ExitOnError<T> for Poll<Result<T, E>>
+where
+    E: Into<Error>,
+{
+    /// Exits the process with an error message if the poll is ready with
+    /// an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the poll is [`Poll::Pending`], since there is no value to
+    /// return yet.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use anyhow::anyhow;
+    /// use eoe::ExitOnError;
+    /// use std::task::Poll;
+    ///
+    /// Poll::Ready(Err::<(), _>(anyhow!("Mm-noom-ba-deh"))).exit_on_error();
+    /// ```
+    fn exit_on_error(self) -> T {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with(failure),
             Ok(value) => value,
         }
     }
@@ -77,15 +519,26 @@ impl<T> ExitOnError<T> for Option<T> {
     /// ```
     fn exit_on_error(self) -> T {
         match self {
-            None => {
-                error!("unexpected None");
-                exit(1);
-            }
+            None => report::exit_with_none(),
             Some(value) => value,
         }
     }
 }
 
+impl<R> ExitOnError<R::Value> for R
+where
+    R: ReportableError,
+{
+    /// Exits the process with an error message if the outcome is an
+    /// error.
+    fn exit_on_error(self) -> R::Value {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with(failure),
+            Ok(value) => value,
+        }
+    }
+}
+
 /// Well, if you prefer the word `quit` to `exit`.
 ///
 /// Quits the process with an error message if the result is an error
@@ -111,8 +564,29 @@ impl<T> ExitOnError<T> for Option<T> {
 /// #
 /// None::<()>.quit_on_error();
 /// ```
-pub trait QuitOnError<T>: internal::Sealed {
+pub trait QuitOnError<T>: internal::Sealed<T> {
     fn quit_on_error(self) -> T;
+
+    /// Same as [`quit_on_error`](QuitOnError::quit_on_error), but quits
+    /// with `code`, taking precedence over [`EXIT_CODE`] and the like.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::QuitOnError;
+    ///
+    /// // EX_NOINPUT
+    /// std::fs::read("Mm-ba-ba-beh").quit_on_error_with_code(66);
+    /// ```
+    fn quit_on_error_with_code(self, code: i32) -> T
+    where
+        Self: Sized,
+    {
+        match self.into_outcome() {
+            Err(failure) => report::exit_with_code(failure, code),
+            Ok(value) => value,
+        }
+    }
 }
 
 impl<T, E> QuitOnError<T> for Result<T, E>
@@ -136,6 +610,73 @@ where
     }
 }
 
+impl<T, E> QuitOnError<Vec<T>> for Vec<Result<T, E>>
+where
+    E: Into<Error>,
+{
+    /// Quits the process with an error message if any of the results is
+    /// an error, otherwise returns the collected values.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::QuitOnError;
+    ///
+    /// vec!["Mm-ba-ba-beh", "2"]
+    ///     .into_iter()
+    ///     .map(str::parse::<i32>)
+    ///     .collect::<Vec<_>>()
+    ///     .quit_on_error();
+    /// ```
+    fn quit_on_error(self) -> Vec<T> {
+        self.exit_on_error()
+    }
+}
+
+impl<T, E, const N: usize> QuitOnError<[T; N]> for [Result<T, E>; N]
+where
+    E: Into<Error>,
+{
+    /// Quits the process with an error message if any of the results is
+    /// an error, otherwise returns the values.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use eoe::QuitOnError;
+    ///
+    /// ["Mm-ba-ba-beh", "2"].map(str::parse::<i32>).quit_on_error();
+    /// ```
+    fn quit_on_error(self) -> [T; N] {
+        self.exit_on_error()
+    }
+}
+
+impl<T, E> QuitOnError<T> for Poll<Result<T, E>>
+where
+    E: Into<Error>,
+{
+    /// Quits the process with an error message if the poll is ready with
+    /// an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the poll is [`Poll::Pending`].
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use anyhow::anyhow;
+    /// use eoe::QuitOnError;
+    /// use std::task::Poll;
+    ///
+    /// Poll::Ready(Err::<(), _>(anyhow!("Mm-ba-ba-beh"))).quit_on_error();
+    /// ```
+    fn quit_on_error(self) -> T {
+        self.exit_on_error()
+    }
+}
+
 impl<T> QuitOnError<T> for Option<T> {
     /// Quits the process with an error message if the option is `None`.
     ///
@@ -148,17 +689,131 @@ impl<T> QuitOnError<T> for Option<T> {
     /// ```
     fn quit_on_error(self) -> T {
         match self {
-            None => {
-                error!("unexpected None");
-                exit(1);
-            }
+            None => report::exit_with_none(),
             Some(value) => value,
         }
     }
 }
 
+impl<R> QuitOnError<R::Value> for R
+where
+    R: ReportableError,
+{
+    /// Quits the process with an error message if the outcome is an
+    /// error.
+    fn quit_on_error(self) -> R::Value {
+        self.exit_on_error()
+    }
+}
+
+/// Extension point for result-like types of your own.
+///
+/// [`ExitOnError`] and [`QuitOnError`] are implemented for every type
+/// implementing this trait.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use anyhow::{anyhow, Error};
+/// use eoe::{ExitOnError, ReportableError};
+///
+/// enum Outcome<T> {
+///     Done(T),
+///     Failed(String),
+/// }
+///
+/// impl<T> ReportableError for Outcome<T> {
+///     type Value = T;
+///
+///     fn into_result(self) -> Result<T, Error> {
+///         match self {
+///             Outcome::Done(value) => Ok(value),
+///             Outcome::Failed(reason) => Err(anyhow!(reason)),
+///         }
+///     }
+/// }
+///
+/// Outcome::<()>::Failed("Mm-noom-ba-deh".to_string()).exit_on_error();
+/// ```
+pub trait ReportableError {
+    /// The value on success.
+    type Value;
+
+    /// Converts into a [`Result`], with the error to report on failure.
+    fn into_result(self) -> Result<Self::Value, Error>;
+}
+
 mod internal {
-    pub trait Sealed {}
-    impl<T, E> Sealed for Result<T, E> where E: Into<super::Error> {}
-    impl<T> Sealed for Option<T> {}
+    use super::Error;
+    use crate::report::Failure;
+    use std::task::Poll;
+
+    pub trait Sealed<T> {
+        fn into_outcome(self) -> Result<T, Failure>;
+    }
+
+    impl<T, E> Sealed<T> for Result<T, E>
+    where
+        E: Into<Error>,
+    {
+        fn into_outcome(self) -> Result<T, Failure> {
+            self.map_err(|error| Failure::Error(error.into()))
+        }
+    }
+
+    impl<T> Sealed<T> for Option<T> {
+        fn into_outcome(self) -> Result<T, Failure> {
+            self.ok_or(Failure::None)
+        }
+    }
+
+    impl<T, E> Sealed<T> for Poll<Result<T, E>>
+    where
+        E: Into<Error>,
+    {
+        fn into_outcome(self) -> Result<T, Failure> {
+            match self {
+                Poll::Ready(result) => result.into_outcome(),
+                Poll::Pending => panic!("called `exit_on_error` on `Poll::Pending`"),
+            }
+        }
+    }
+
+    impl<R> Sealed<R::Value> for R
+    where
+        R: super::ReportableError,
+    {
+        fn into_outcome(self) -> Result<R::Value, Failure> {
+            self.into_result().map_err(Failure::Error)
+        }
+    }
+
+    impl<T, E> Sealed<Vec<T>> for Vec<Result<T, E>>
+    where
+        E: Into<Error>,
+    {
+        fn into_outcome(self) -> Result<Vec<T>, Failure> {
+            if self.iter().any(Result::is_err) {
+                let errors = self.into_iter().filter_map(Result::err);
+                return Err(Failure::Errors(errors.map(Into::into).collect()));
+            }
+            Ok(self
+                .into_iter()
+                .map(|result| result.ok().unwrap())
+                .collect())
+        }
+    }
+
+    impl<T, E, const N: usize> Sealed<[T; N]> for [Result<T, E>; N]
+    where
+        E: Into<Error>,
+    {
+        fn into_outcome(self) -> Result<[T; N], Failure> {
+            if self.iter().any(Result::is_err) {
+                let errors = self.into_iter().filter_map(Result::err);
+                return Err(Failure::Errors(errors.map(Into::into).collect()));
+            }
+            Ok(self.map(|result| result.ok().unwrap()))
+        }
+    }
 }
@@ -1,19 +1,20 @@
-macro_rules! error {
-    ($($arg:tt)*) => {
-        std::eprintln!(
-            "{}: {}",
-            ansi_term::Colour::Red.bold().paint("error"),
-            std::format_args!("{}", $($arg)*)
-        )
-    };
-}
-
-macro_rules! caused_by {
-    ($($arg:tt)*) => {
-        std::eprintln!(
-            "{}: {}",
-            ansi_term::Colour::Red.bold().paint("caused by"),
-            std::format_args!("{}", $($arg)*)
-        )
-    };
+/// Sets [`CRATE_PREFIX`](crate::CRATE_PREFIX) to the name of the calling
+/// package, as baked in at compile time from `CARGO_PKG_NAME`.
+///
+/// Has no effect if the prefix is already set.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::ExitOnError;
+///
+/// eoe::set_crate_prefix!();
+///
+/// None::<()>.exit_on_error();
+/// ```
+#[macro_export]
+macro_rules! set_crate_prefix {
+    () => {{
+        let _ = $crate::CRATE_PREFIX.set(::core::env!("CARGO_PKG_NAME"));
+    }};
 }
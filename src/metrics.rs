@@ -0,0 +1,26 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TERMINATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static LAST_EXIT_CODE: Cell<Option<i32>> = const { Cell::new(None) };
+}
+
+/// Returns how many times
+/// [`exit_on_error_reporting`](crate::ExitOnError::exit_on_error_reporting)
+/// has gone down the error path, across all threads.
+pub fn termination_count() -> usize {
+    TERMINATIONS.load(Ordering::Relaxed)
+}
+
+/// Returns the exit code most recently recorded on the current thread by
+/// [`exit_on_error_reporting`](crate::ExitOnError::exit_on_error_reporting).
+pub fn last_exit_code() -> Option<i32> {
+    LAST_EXIT_CODE.with(Cell::get)
+}
+
+pub(crate) fn record(code: i32) {
+    TERMINATIONS.fetch_add(1, Ordering::Relaxed);
+    LAST_EXIT_CODE.with(|last| last.set(Some(code)));
+}
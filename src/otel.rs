@@ -0,0 +1,23 @@
+use crate::render;
+use anyhow::Error;
+use opentelemetry::trace::get_active_span;
+use opentelemetry::KeyValue;
+use std::backtrace::BacktraceStatus;
+
+/// Records `error` as an exception event on the current span, if any.
+pub(crate) fn record(error: &Error) {
+    get_active_span(|span| {
+        let mut attributes = vec![
+            KeyValue::new(
+                "exception.type",
+                render::error_type(error).unwrap_or("anyhow::Error"),
+            ),
+            KeyValue::new("exception.message", format!("{:#}", error)),
+        ];
+        let backtrace = error.backtrace();
+        if backtrace.status() == BacktraceStatus::Captured {
+            attributes.push(KeyValue::new("exception.stacktrace", backtrace.to_string()));
+        }
+        span.add_event("exception", attributes);
+    });
+}
@@ -0,0 +1,309 @@
+use crate::config::{
+    ColorChoice, Fallback, OnWriteError, Stream, COLOR, DRY_RUN, ERROR_STREAM, EXIT_DELAY,
+    FORCE_NO_TTY, FORCE_TTY, LOCK_TIMEOUT, MAX_OUTPUT_BYTES, ON_WRITE_ERROR, REPORT_SINK,
+};
+use std::cell::RefCell;
+use std::io::{self, stderr, stdout, ErrorKind, IsTerminal, Write};
+use std::panic::resume_unwind;
+use std::process;
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::thread;
+
+thread_local! {
+    static CAPTURE: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+    static PENDING: RefCell<Vec<(Stream, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Payload unwound in place of exiting while output is being captured.
+#[cfg_attr(not(feature = "testing"), allow(dead_code))]
+pub(crate) struct Exit(pub(crate) i32);
+
+/// Payload unwound in place of exiting if [`DRY_RUN`] is on.
+#[derive(Debug)]
+pub struct DryRunExit {
+    /// What would have been printed.
+    pub output: String,
+    /// The code the process would have exited with.
+    pub code: i32,
+}
+
+/// Starts capturing on the current thread, returning the previous buffer.
+#[cfg_attr(not(feature = "testing"), allow(dead_code))]
+pub(crate) fn start_capture() -> Option<Vec<u8>> {
+    CAPTURE.with(|capture| capture.replace(Some(Vec::new())))
+}
+
+/// Stops capturing on the current thread, restoring the previous buffer.
+#[cfg_attr(not(feature = "testing"), allow(dead_code))]
+pub(crate) fn stop_capture(previous: Option<Vec<u8>>) -> Vec<u8> {
+    CAPTURE
+        .with(|capture| capture.replace(previous))
+        .unwrap_or_default()
+}
+
+/// Returns whether the output will be painted with colors, according to
+/// [`COLOR`] and whether the [`ERROR_STREAM`] is a terminal (see
+/// [`FORCE_NO_TTY`] and [`FORCE_TTY`]).
+///
+/// # Examples
+///
+/// ```
+/// use eoe::{will_use_color, ColorChoice, COLOR};
+///
+/// // Querying does not lock in the defaults.
+/// will_use_color();
+/// COLOR.set(ColorChoice::Always).unwrap();
+///
+/// assert!(will_use_color());
+/// ```
+pub fn will_use_color() -> bool {
+    will_use_color_on(
+        ERROR_STREAM
+            .get()
+            .copied()
+            .unwrap_or(Fallback::ERROR_STREAM),
+    )
+}
+
+/// Same as [`will_use_color`], but for output written to `stream`.
+pub(crate) fn will_use_color_on(stream: Stream) -> bool {
+    match COLOR.get().copied().unwrap_or(Fallback::COLOR) {
+        ColorChoice::Auto => is_terminal(stream),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    }
+}
+
+/// Returns whether `stream` is a terminal, unless overridden by
+/// [`FORCE_NO_TTY`] or [`FORCE_TTY`].
+pub(crate) fn is_terminal(stream: Stream) -> bool {
+    if FORCE_NO_TTY
+        .get()
+        .copied()
+        .unwrap_or(Fallback::FORCE_NO_TTY)
+    {
+        return false;
+    }
+    if FORCE_TTY.get().copied().unwrap_or(Fallback::FORCE_TTY) {
+        return true;
+    }
+    let capturing = CAPTURE.with(|capture| capture.borrow().is_some());
+    !capturing
+        && match stream {
+            Stream::Stderr => stderr().is_terminal(),
+            Stream::Stdout => stdout().is_terminal(),
+        }
+}
+
+/// Queues `text` for `stream`, to be written out by [`flush`].
+pub(crate) fn print_to(stream: Stream, text: &str) {
+    PENDING.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        match pending.last_mut() {
+            Some((last, queued)) if *last == stream => queued.push_str(text),
+            _ => pending.push((stream, text.to_string())),
+        }
+    });
+}
+
+/// Writes out the queued text, truncated to [`MAX_OUTPUT_BYTES`], or hands
+/// it to [`REPORT_SINK`] if set.
+///
+/// Stops at, and returns, [`BrokenPipe`] if an output stream is a broken
+/// pipe, leaving it to the caller to [`exit_broken_pipe`].
+///
+/// Under [`DRY_RUN`], the text is held instead, see [`flush_now`].
+pub(crate) fn flush() -> Result<(), BrokenPipe> {
+    if is_dry_run() {
+        return Ok(());
+    }
+    flush_now()
+}
+
+/// Same as [`flush`], but writes the queued text out even under
+/// [`DRY_RUN`], e.g. for reports that no exit will collect.
+pub(crate) fn flush_now() -> Result<(), BrokenPipe> {
+    let chunks = take();
+    if chunks.is_empty() {
+        return Ok(());
+    }
+    let captured = CAPTURE.with(|capture| match capture.borrow_mut().as_mut() {
+        Some(buffer) => {
+            for (_, output) in &chunks {
+                buffer.extend_from_slice(output.as_bytes());
+            }
+            true
+        }
+        None => false,
+    });
+    if captured {
+        return Ok(());
+    }
+    if let Some(sink) = REPORT_SINK.get() {
+        sink(&join(chunks));
+        return Ok(());
+    }
+    for (stream, output) in chunks {
+        let result = match stream {
+            Stream::Stderr => write(output),
+            Stream::Stdout => stdout().lock().write_all(output.as_bytes()),
+        };
+        if let Err(error) = result {
+            if error.kind() == ErrorKind::BrokenPipe {
+                return Err(BrokenPipe);
+            }
+            match ON_WRITE_ERROR.get_or_init(|| Fallback::ON_WRITE_ERROR) {
+                OnWriteError::Panic => panic!("failed printing the report: {}", error),
+                OnWriteError::Ignore => {}
+                OnWriteError::Abort => process::abort(),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// An output stream was a broken pipe while [flushing](flush).
+pub(crate) struct BrokenPipe;
+
+/// Exits quietly with `141` after a [`BrokenPipe`].
+pub(crate) fn exit_broken_pipe() -> ! {
+    // 128 + SIGPIPE, as if killed by the signal like most Unix tools in a
+    // pipeline.
+    process::exit(141)
+}
+
+/// Takes the queued text, by stream, truncated to [`MAX_OUTPUT_BYTES`]
+/// in total.
+fn take() -> Vec<(Stream, String)> {
+    let mut chunks = PENDING.with(|pending| pending.take());
+    if let Some(&max) = MAX_OUTPUT_BYTES.get() {
+        let mut remaining = max;
+        for index in 0..chunks.len() {
+            let output = &mut chunks[index].1;
+            if output.len() > remaining {
+                truncate(output, remaining);
+                chunks.truncate(index + 1);
+                break;
+            }
+            remaining -= output.len();
+        }
+    }
+    chunks
+}
+
+fn join(chunks: Vec<(Stream, String)>) -> String {
+    chunks.into_iter().map(|(_, output)| output).collect()
+}
+
+/// Writes `output` to standard error, falling back to standard output if
+/// the lock cannot be acquired within [`LOCK_TIMEOUT`].
+fn write(output: String) -> io::Result<()> {
+    let timeout = match LOCK_TIMEOUT.get() {
+        Some(&timeout) => timeout,
+        None => return stderr().lock().write_all(output.as_bytes()),
+    };
+
+    // Whoever claims first gets to write, so that the output never shows
+    // up twice should the lock be acquired right after timing out.
+    let claimed = Arc::new(Mutex::new(false));
+    let (sender, receiver) = mpsc::channel();
+    let output = Arc::new(output);
+    {
+        let claimed = Arc::clone(&claimed);
+        let output = Arc::clone(&output);
+        thread::spawn(move || {
+            let mut stderr = stderr().lock();
+            if claim(&claimed) {
+                let _ = sender.send(stderr.write_all(output.as_bytes()));
+            }
+        });
+    }
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(_) if claim(&claimed) => stdout().lock().write_all(output.as_bytes()),
+        Err(_) => receiver.recv().unwrap_or(Ok(())),
+    }
+}
+
+fn claim(claimed: &Mutex<bool>) -> bool {
+    let mut claimed = claimed.lock().unwrap_or_else(PoisonError::into_inner);
+    !std::mem::replace(&mut *claimed, true)
+}
+
+fn truncate(output: &mut String, max: usize) {
+    if output.len() <= max {
+        return;
+    }
+    let mut index = max;
+    while !output.is_char_boundary(index) {
+        index -= 1;
+    }
+    output.truncate(index);
+    // Cutting an escape sequence short would have the terminal swallow the
+    // text after it, and one left open would style it.
+    while let Some(start) = output.rfind('\x1b') {
+        if is_complete(&output[start..]) {
+            break;
+        }
+        output.truncate(start);
+    }
+    if output.contains("\x1b]8;") {
+        output.push_str("\x1b]8;;\x1b\\");
+    }
+    if output.contains('\x1b') {
+        output.push_str("\x1b[0m");
+    }
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+    output.push_str("… (output truncated)\n");
+}
+
+/// Returns whether `text`, starting with the last escape character of the
+/// output, holds a complete escape sequence.
+fn is_complete(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    match bytes.get(1) {
+        None => false,
+        // A CSI sequence ends with its first byte in `@..=~`, any before
+        // being parameters.
+        Some(b'[') => bytes[2..].iter().any(|byte| (0x40..=0x7e).contains(byte)),
+        // An OSC sequence ends with BEL, or with `ESC \`, which would be
+        // found as the last escape instead.
+        Some(b']') => bytes[2..].contains(&0x07),
+        Some(_) => true,
+    }
+}
+
+/// Returns whether [`DRY_RUN`] applies, i.e. is on and output is not being
+/// captured, in which case the queued text is held until exiting.
+pub(crate) fn is_dry_run() -> bool {
+    let capturing = CAPTURE.with(|capture| capture.borrow().is_some());
+    !capturing && *DRY_RUN.get_or_init(|| Fallback::DRY_RUN)
+}
+
+/// Returns whether exiting only unwinds, i.e. [`DRY_RUN`] applies or output
+/// is being captured.
+pub(crate) fn is_simulated() -> bool {
+    is_dry_run() || CAPTURE.with(|capture| capture.borrow().is_some())
+}
+
+pub(crate) fn exit(code: i32) -> ! {
+    if is_dry_run() {
+        resume_unwind(Box::new(DryRunExit {
+            output: join(take()),
+            code,
+        }));
+    }
+    if flush().is_err() {
+        exit_broken_pipe();
+    }
+    if CAPTURE.with(|capture| capture.borrow().is_some()) {
+        resume_unwind(Box::new(Exit(code)));
+    }
+    let delay = *EXIT_DELAY.get_or_init(|| Fallback::EXIT_DELAY);
+    if !delay.is_zero() {
+        thread::sleep(delay);
+    }
+    process::exit(code)
+}
@@ -0,0 +1,86 @@
+use crate::output;
+use crate::render::{Line, Severity};
+use crate::report;
+use std::panic::{self, PanicHookInfo};
+
+/// Installs a panic hook reporting panics in the same style as errors.
+///
+/// The report is written when the panic happens, even under
+/// [`DRY_RUN`](crate::DRY_RUN).
+///
+/// # Examples
+///
+/// ```should_panic
+/// eoe::install_panic_hook();
+///
+/// panic!("Mm-noom-ba-deh");
+/// ```
+///
+/// ```
+/// use eoe::{DryRunExit, ExitOnError, DRY_RUN};
+/// use std::panic::catch_unwind;
+///
+/// DRY_RUN.set(true).unwrap();
+/// eoe::install_panic_hook();
+///
+/// let _ = catch_unwind(|| panic!("Mm-noom-ba-deh"));
+///
+/// let payload = catch_unwind(|| None::<()>.exit_on_error()).unwrap_err();
+/// let exit = payload.downcast::<DryRunExit>().unwrap();
+/// assert_eq!(exit.output, "error: unexpected None\n");
+/// ```
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| print(lines(info))));
+}
+
+/// Same as [`install_panic_hook`], but also writes a crash report with
+/// [`human_panic`], the path to which is mentioned in the output.
+///
+/// Requires the `human-panic` feature.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::human_panic::metadata;
+///
+/// eoe::install_panic_hook_with_crash_report(metadata!());
+///
+/// panic!("Mm-noom-ba-deh");
+/// ```
+#[cfg(feature = "human-panic")]
+pub fn install_panic_hook_with_crash_report(metadata: human_panic::Metadata) {
+    panic::set_hook(Box::new(move |info| {
+        let mut lines = lines(info);
+        if let Some(path) = human_panic::handle_dump(&metadata, info) {
+            let message = format!("a crash report was written to {}", path.display());
+            lines.push(Line::new(Severity::Note, 0, message));
+        }
+        print(lines);
+    }));
+}
+
+fn lines(info: &PanicHookInfo) -> Vec<Line> {
+    let payload = info.payload();
+    let message = match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "Box<dyn Any>".to_string(),
+        },
+    };
+    let mut lines = vec![Line::new(Severity::Error, 0, message)];
+    if let Some(location) = info.location() {
+        let message = format!("panicked at {}", location);
+        lines.push(Line::new(Severity::Note, 0, message));
+    }
+    lines
+}
+
+fn print(lines: Vec<Line>) {
+    report::print_lines(lines, None);
+    // Written out right away even under `DRY_RUN`, lest the report end up
+    // in the output of an unrelated exit.
+    if output::flush_now().is_err() {
+        output::exit_broken_pipe();
+    }
+}
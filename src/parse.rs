@@ -0,0 +1,26 @@
+use crate::report;
+use anyhow::anyhow;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Parses `s`, exiting the process with
+/// `error: failed to parse '<s>': <error>` on failure.
+///
+/// Unlike [`ExitOnError`](crate::ExitOnError), this only requires the
+/// error to implement [`Display`].
+///
+/// # Examples
+///
+/// ```should_panic
+/// let port: u16 = eoe::parse_or_exit("Mm-noom-ba-deh");
+/// ```
+pub fn parse_or_exit<T>(s: &str) -> T
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match s.parse() {
+        Err(error) => report::exit_with_error(anyhow!("failed to parse '{}': {}", s, error)),
+        Ok(value) => value,
+    }
+}
@@ -0,0 +1,690 @@
+use crate::clock;
+use crate::config::{
+    Background, ColorChoice, Fallback, Format, IconSet, Layout, LineKind, SepStyleSource, Stream,
+    BACKGROUND, CAUSED_BY_SEP, CAUSE_SPACING, COLOR, CONNECTIVES, CRATE_PREFIX, ELAPSED, ERROR_SEP,
+    ERROR_STREAM, FIRST_LINE_ONLY, FORMAT, GLOBAL_PREFIX, ICON_SET, LAYOUT, LINE_PREFIX_FN,
+    NOTE_SEP, NOTE_STREAM, POST_SEP, PRE_SEP, REPORT_ALL_ERRORS, SEP, SEP_STYLE_SOURCE,
+    SHOW_CAUSE_COUNT, SHOW_ERROR_TYPE, SHOW_IO_DETAILS, SUBSYSTEM, SUGGEST_BACKTRACE,
+};
+use crate::help;
+use crate::output;
+use crate::report::{self, Failure};
+use crate::segment::{Segment, PLAIN};
+use ansi_term::Style;
+use anyhow::Error;
+use std::backtrace::BacktraceStatus;
+use std::error::Error as StdError;
+use std::io::{self, Write};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Error,
+    CausedBy,
+    Note,
+    /// Header of a group of causes, without a label of its own.
+    Group,
+}
+
+impl Severity {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::CausedBy => "caused by",
+            Self::Note => "note",
+            Self::Group => "",
+        }
+    }
+
+    fn icon(self) -> Option<&'static str> {
+        let icon = match (ICON_SET.get_or_init(|| Fallback::ICON_SET), self) {
+            (IconSet::None, _) | (_, Self::Group) => return None,
+            (IconSet::Unicode, Self::Error) => "✖",
+            (IconSet::Unicode, Self::CausedBy) => "↳",
+            (IconSet::Unicode, Self::Note) => "ℹ",
+            (IconSet::Ascii, Self::Error) => "[E]",
+            (IconSet::Ascii, Self::CausedBy) => "[C]",
+            (IconSet::Ascii, Self::Note) => "[N]",
+        };
+        Some(icon)
+    }
+
+    fn kind(self) -> LineKind {
+        match self {
+            Self::Error => LineKind::Error,
+            Self::CausedBy => LineKind::Cause,
+            Self::Note => LineKind::Note,
+            Self::Group => LineKind::Group,
+        }
+    }
+
+    pub(crate) fn stream(self) -> Stream {
+        match self {
+            Self::Error | Self::CausedBy | Self::Group => {
+                *ERROR_STREAM.get_or_init(|| Fallback::ERROR_STREAM)
+            }
+            Self::Note => *NOTE_STREAM.get_or_init(|| Fallback::NOTE_STREAM),
+        }
+    }
+
+    fn style(self) -> Style {
+        match self {
+            Self::Error | Self::CausedBy | Self::Group => label_style(),
+            Self::Note => Fallback::NOTE_STYLE,
+        }
+    }
+
+    fn sep(self) -> &'static Segment {
+        let sep = match self {
+            Self::Error => ERROR_SEP.get(),
+            Self::CausedBy => CAUSED_BY_SEP.get(),
+            Self::Note => NOTE_SEP.get(),
+            Self::Group => None,
+        };
+        sep.unwrap_or_else(|| SEP.get_or_init(Fallback::sep))
+    }
+}
+
+/// A line of the report, before presentation.
+pub(crate) struct Line {
+    pub(crate) severity: Severity,
+    /// Position in the chain, `0` being the error itself.
+    pub(crate) depth: usize,
+    /// Number of causes in the chain the line belongs to.
+    pub(crate) causes: usize,
+    pub(crate) message: String,
+    /// Page the label links to, e.g. documentation.
+    pub(crate) url: Option<String>,
+    /// URL mentioned in the message, made a link to itself.
+    pub(crate) message_url: Option<String>,
+}
+
+impl Line {
+    pub(crate) fn new(severity: Severity, depth: usize, message: String) -> Self {
+        Self {
+            severity,
+            depth,
+            causes: 0,
+            message,
+            url: None,
+            message_url: None,
+        }
+    }
+}
+
+/// Returns the lines reporting `failure`.
+pub(crate) fn lines(failure: &Failure) -> Vec<Line> {
+    failure_lines(failure, false)
+}
+
+/// Same as [`lines`], but with the root cause rendered through `Debug`.
+pub(crate) fn debug_lines(failure: &Failure) -> Vec<Line> {
+    failure_lines(failure, true)
+}
+
+fn failure_lines(failure: &Failure, debug: bool) -> Vec<Line> {
+    let errors = match failure {
+        Failure::Error(error) => std::slice::from_ref(error),
+        Failure::Errors(errors) => {
+            if *REPORT_ALL_ERRORS.get_or_init(|| Fallback::REPORT_ALL_ERRORS) {
+                errors.as_slice()
+            } else {
+                &errors[..1]
+            }
+        }
+        Failure::None => {
+            let message = with_elapsed("unexpected None".to_string());
+            return vec![Line::new(Severity::Error, 0, message)];
+        }
+    };
+    errors_lines(errors, debug)
+}
+
+/// Returns the lines reporting `errors` one after another.
+pub(crate) fn report_lines(errors: &[Error]) -> Vec<Line> {
+    errors_lines(errors, false)
+}
+
+fn errors_lines(errors: &[Error], debug: bool) -> Vec<Line> {
+    let mut lines: Vec<Line> = errors
+        .iter()
+        .flat_map(|error| error_lines(error, debug))
+        .collect();
+    if *SUGGEST_BACKTRACE.get_or_init(|| Fallback::SUGGEST_BACKTRACE) {
+        let backtraces: Vec<_> = errors
+            .iter()
+            .map(Error::backtrace)
+            .filter(|backtrace| backtrace.status() == BacktraceStatus::Captured)
+            .collect();
+        if backtraces.is_empty() {
+            let message = "run with RUST_BACKTRACE=1 for a backtrace".to_string();
+            lines.push(Line::new(Severity::Note, 0, message));
+        }
+        for backtrace in backtraces {
+            let message = format!("backtrace:\n{}", backtrace);
+            lines.push(Line::new(Severity::Note, 0, message));
+        }
+    }
+    lines
+}
+
+fn error_lines(error: &Error, debug: bool) -> Vec<Line> {
+    let show_io_details = *SHOW_IO_DETAILS.get_or_init(|| Fallback::SHOW_IO_DETAILS);
+    let show_error_type = *SHOW_ERROR_TYPE.get_or_init(|| Fallback::SHOW_ERROR_TYPE);
+    let first_line_only = *FIRST_LINE_ONLY.get_or_init(|| Fallback::FIRST_LINE_ONLY);
+    let root = error.chain().count() - 1;
+    let mut lines = Vec::new();
+    for (depth, cause) in error.chain().enumerate() {
+        // Context layers print their whole source chain through `Debug`,
+        // so only the root cause, being the concrete error, goes through
+        // it.
+        let mut message = if debug && depth == root {
+            format!("{:?}", cause)
+        } else {
+            cause.to_string()
+        };
+        // Before the suffixes below, so that they are kept.
+        if first_line_only {
+            if let Some(index) = message.find('\n') {
+                message.truncate(index);
+                message.push('…');
+            }
+        }
+        if show_io_details {
+            if let Some(error) = cause.downcast_ref::<io::Error>() {
+                message = format!("{} (kind: {:?})", message, error.kind());
+            }
+        }
+        let severity = if depth == 0 {
+            if show_error_type {
+                if let Some(name) = error_type(error) {
+                    message = format!("{} [{}]", message, name);
+                }
+            }
+            message = with_elapsed(message);
+            Severity::Error
+        } else {
+            Severity::CausedBy
+        };
+        lines.push(Line::new(severity, depth, message));
+    }
+    let causes = lines.len() - 1;
+    lines.iter_mut().for_each(|line| line.causes = causes);
+    lines[0].url = help::help_url(error);
+    lines
+}
+
+fn with_elapsed(message: String) -> String {
+    if *ELAPSED.get_or_init(|| Fallback::ELAPSED) {
+        format!("{} (after {:.1}s)", message, clock::elapsed().as_secs_f64())
+    } else {
+        message
+    }
+}
+
+/// Renders a single line, prefixes included.
+fn render_line(line: &Line, color: bool) -> String {
+    let (prefix, body) = line_parts(line, color);
+    prefix + &body
+}
+
+/// Renders a single line as its prefix and the rest.
+fn line_parts(line: &Line, color: bool) -> (String, String) {
+    let sep = line.severity.sep();
+    let mut message = line.message.clone();
+    if let (Some(url), true) = (&line.message_url, color) {
+        if let Some(index) = message.rfind(url.as_str()) {
+            message.replace_range(index..index + url.len(), &hyperlink(url, url));
+        }
+    }
+    let prefix = line_prefix(line, false);
+    if message.contains('\n') {
+        message = message.replace('\n', &format!("\n{}", line_prefix(line, true)));
+    }
+    if line.severity == Severity::Group {
+        let body = paint(line.severity.style(), &format!("{}:", message), color);
+        return (prefix, body);
+    }
+    let (mut label, label_style) = match (line.severity, CONNECTIVES.get()) {
+        (Severity::CausedBy, Some(connectives)) if !connectives.is_empty() => {
+            let index = line.depth.saturating_sub(1).min(connectives.len() - 1);
+            let connective = &connectives[index];
+            (connective.text.clone(), connective.style)
+        }
+        (severity, _) => (severity.label().to_string(), severity.style()),
+    };
+    if line.severity == Severity::CausedBy
+        && *SHOW_CAUSE_COUNT.get_or_init(|| Fallback::SHOW_CAUSE_COUNT)
+    {
+        label = format!("{} ({}/{})", label, line.depth, line.causes);
+    }
+    if let (Severity::Error, Some(tag)) = (line.severity, SUBSYSTEM.get()) {
+        label = format!("{}[{}]", label, tag);
+    }
+    if let Some(icon) = line.severity.icon() {
+        label = format!("{} {}", icon, label);
+    }
+    let sep_style = match SEP_STYLE_SOURCE.get_or_init(|| Fallback::SEP_STYLE_SOURCE) {
+        SepStyleSource::Own => sep.style,
+        SepStyleSource::LikeLabel => label_style,
+        SepStyleSource::LikeMessage => PLAIN,
+    };
+    let mut label = paint(label_style, &label, color);
+    if let (Some(url), true) = (&line.url, color) {
+        label = hyperlink(url, &label);
+    }
+    let body = format!(
+        "{}{}{}{}{}",
+        label,
+        PRE_SEP.get_or_init(|| Fallback::PRE_SEP),
+        paint(sep_style, sep.text, color),
+        POST_SEP.get_or_init(|| Fallback::POST_SEP),
+        message
+    );
+    (prefix, body)
+}
+
+/// Returns the prefix of `line`, or of its lines after the first one if
+/// `continued`, which go without the [`CRATE_PREFIX`].
+fn line_prefix(line: &Line, continued: bool) -> String {
+    let global = GLOBAL_PREFIX.get_or_init(|| Fallback::GLOBAL_PREFIX);
+    match (LINE_PREFIX_FN.get(), CRATE_PREFIX.get()) {
+        (Some(prefix_fn), _) => prefix_fn(line.severity.kind(), line.depth),
+        (None, Some(prefix)) if !continued => format!("{}{}: ", global, prefix),
+        (None, _) => global.to_string(),
+    }
+}
+
+/// Wraps `text` in an OSC 8 hyperlink to `url`.
+fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// Renders `lines` into a report, without a trailing newline.
+pub(crate) fn render_lines(lines: &[Line], color: bool) -> String {
+    rendered_lines(lines, color).join("\n")
+}
+
+/// Renders `lines` one by one, along with the blank lines and borders
+/// around them.
+fn rendered_lines(lines: &[Line], color: bool) -> Vec<String> {
+    let cause_spacing = *CAUSE_SPACING.get_or_init(|| Fallback::CAUSE_SPACING);
+    let boxed = *LAYOUT.get_or_init(|| Fallback::LAYOUT) == Layout::BoxedHeadline;
+    let mut rendered = Vec::new();
+    for line in lines {
+        if cause_spacing && line.severity == Severity::CausedBy && line.depth > 1 {
+            rendered.push(line_prefix(line, false));
+        }
+        if boxed && line.severity == Severity::Error {
+            rendered.extend(boxed_line(line, color));
+        } else {
+            rendered.push(render_line(line, color));
+        }
+    }
+    rendered
+}
+
+/// Renders `lines` as one JSON object each, without a trailing newline.
+///
+/// `code` is included in the objects of `error` lines if known.
+pub(crate) fn render_json_lines(lines: &[Line], code: Option<i32>) -> String {
+    let objects: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            let message = json_string(&line.message);
+            match line.severity {
+                Severity::Error => match code {
+                    Some(code) => format!(
+                        r#"{{"level":"error","msg":{},"exit_code":{}}}"#,
+                        message, code
+                    ),
+                    None => format!(r#"{{"level":"error","msg":{}}}"#, message),
+                },
+                Severity::CausedBy => format!(
+                    r#"{{"level":"caused_by","depth":{},"msg":{}}}"#,
+                    line.depth, message
+                ),
+                Severity::Note => format!(r#"{{"level":"note","msg":{}}}"#, message),
+                Severity::Group => format!(r#"{{"level":"group","msg":{}}}"#, message),
+            }
+        })
+        .collect();
+    objects.join("\n")
+}
+
+/// Renders `error` as a single JSON object with its message, causes and
+/// `code`.
+pub(crate) fn status_json(error: &Error, code: i32) -> String {
+    let causes: Vec<String> = error
+        .chain()
+        .skip(1)
+        .map(|cause| json_string(&cause.to_string()))
+        .collect();
+    format!(
+        r#"{{"message":{},"causes":[{}],"exit_code":{}}}"#,
+        json_string(&error.to_string()),
+        causes.join(","),
+        code
+    )
+}
+
+pub(crate) fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Renders a single line drawn in a box, border lines included, with its
+/// prefix in front of each row rather than inside the box.
+fn boxed_line(line: &Line, color: bool) -> [String; 3] {
+    let (prefix, body) = line_parts(line, color);
+    // Wide characters and line breaks throw this off, see
+    // `Layout::BoxedHeadline`.
+    let width = line_parts(line, false).1.chars().count() + 2;
+    let border = "─".repeat(width);
+    [
+        format!("{}╭{}╮", prefix, border),
+        format!("{}│ {} │", prefix, body),
+        format!("{}╰{}╯", prefix, border),
+    ]
+}
+
+/// Regroups the causes of each error under headers, according to the
+/// category `classify` assigns to their message.
+///
+/// Groups are ordered by first appearance, and causes assigned an empty
+/// category end up in a trailing `Other` group.
+pub(crate) fn group<F>(lines: Vec<Line>, classify: F) -> Vec<Line>
+where
+    F: Fn(&str) -> &'static str,
+{
+    fn flush(grouped: &mut Vec<Line>, groups: &mut Vec<(&'static str, Vec<Line>)>) {
+        if let Some(index) = groups.iter().position(|(category, _)| category.is_empty()) {
+            let other = groups.remove(index);
+            groups.push(("Other", other.1));
+        }
+        for (category, causes) in groups.drain(..) {
+            grouped.push(Line::new(Severity::Group, 0, category.to_string()));
+            grouped.extend(causes);
+        }
+    }
+
+    let mut grouped = Vec::new();
+    let mut groups: Vec<(&'static str, Vec<Line>)> = Vec::new();
+    for line in lines {
+        if line.severity != Severity::CausedBy {
+            flush(&mut grouped, &mut groups);
+            grouped.push(line);
+            continue;
+        }
+        let category = classify(&line.message);
+        match groups
+            .iter_mut()
+            .find(|(existing, _)| *existing == category)
+        {
+            Some((_, causes)) => causes.push(line),
+            None => groups.push((category, vec![line])),
+        }
+    }
+    flush(&mut grouped, &mut groups);
+    grouped
+}
+
+/// Renders the lines reporting `error` in the human format, colors
+/// included according to [`will_use_color`](crate::will_use_color).
+///
+/// Unlike [`write_error`], this ignores [`FORMAT`](crate::FORMAT) and
+/// leaves out the notes and the reason line that follow the report when
+/// printed. The returned string has no trailing newline.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::{format_error, ColorChoice, COLOR};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     format_error(&error),
+///     "error: Doom-boom-ba-beh\ncaused by: Mm-noom-ba-deh"
+/// );
+/// ```
+pub fn format_error(error: &Error) -> String {
+    let lines = report_lines(std::slice::from_ref(error));
+    render_lines(&lines, output::will_use_color())
+}
+
+/// Writes the report for `error` to `writer` as
+/// [`exit_on_error`](crate::ExitOnError::exit_on_error) prints it, trailing
+/// newline included.
+///
+/// Every line goes to `writer`, regardless of
+/// [`ERROR_STREAM`](crate::ERROR_STREAM) and
+/// [`NOTE_STREAM`](crate::NOTE_STREAM). Since `writer` may be anything,
+/// the report is only colored if [`COLOR`](crate::COLOR) is
+/// [`ColorChoice::Always`](crate::ColorChoice).
+///
+/// # Errors
+///
+/// Fails if writing to `writer` fails.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::write_error;
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .unwrap_err();
+///
+/// let mut buffer = Vec::new();
+/// write_error(&mut buffer, &error).unwrap();
+/// assert_eq!(buffer, b"error: Doom-boom-ba-beh\ncaused by: Mm-noom-ba-deh\n");
+/// ```
+///
+/// ```
+/// use anyhow::anyhow;
+/// use eoe::{write_error, REASON_FN, SHOW_EXIT_CODE};
+///
+/// SHOW_EXIT_CODE.set(true).unwrap();
+/// REASON_FN.set(Box::new(|_| Some("network"))).ok();
+///
+/// let mut buffer = Vec::new();
+/// write_error(&mut buffer, &anyhow!("Mm-noom-ba-deh")).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buffer).unwrap(),
+///     "error: Mm-noom-ba-deh\nnote: exiting with code 1\nEOE_REASON=network\n"
+/// );
+/// ```
+pub fn write_error<W>(writer: &mut W, error: &Error) -> io::Result<()>
+where
+    W: Write,
+{
+    let lines = report_lines(std::slice::from_ref(error));
+    let code = report::error_exit_code(error);
+    write_report(writer, lines, code, report::reason(error))
+}
+
+/// Same as [`write_error`], but for an unexpected `None`.
+///
+/// # Errors
+///
+/// Fails if writing to `writer` fails.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::write_none;
+///
+/// let mut buffer = Vec::new();
+/// write_none(&mut buffer).unwrap();
+/// assert_eq!(buffer, b"error: unexpected None\n");
+/// ```
+pub fn write_none<W>(writer: &mut W) -> io::Result<()>
+where
+    W: Write,
+{
+    let code = report::exit_code(&Failure::None);
+    let reason = Failure::None.with_error(report::reason);
+    write_report(writer, lines(&Failure::None), code, reason)
+}
+
+fn write_report<W>(
+    writer: &mut W,
+    lines: Vec<Line>,
+    code: i32,
+    reason: Option<&str>,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    let color = *COLOR.get_or_init(|| Fallback::COLOR) == ColorChoice::Always;
+    for (_, report) in report::render_report(lines, Some(code), reason, |_| color) {
+        writer.write_all(report.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Renders the report for `error` along with the code the process would
+/// exit with, for reporting errors without terminating, e.g. in a server.
+///
+/// The report follows [`FORMAT`](crate::FORMAT), and is only colored if
+/// [`COLOR`](crate::COLOR) is [`ColorChoice::Always`](crate::ColorChoice).
+/// It has no trailing newline.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::{render_with_code, IO_EXIT_CODES};
+///
+/// IO_EXIT_CODES.set(true).unwrap();
+///
+/// let error = std::fs::read("Mm-noom-ba-deh").unwrap_err().into();
+/// let (report, code) = render_with_code(&error);
+/// assert!(report.starts_with("error: "));
+/// assert_eq!(code, 66);
+/// ```
+pub fn render_with_code(error: &Error) -> (String, i32) {
+    let code = report::error_exit_code(error);
+    let lines = report_lines(std::slice::from_ref(error));
+    let report = match FORMAT.get_or_init(|| Fallback::FORMAT) {
+        Format::Human => {
+            let color = *COLOR.get_or_init(|| Fallback::COLOR) == ColorChoice::Always;
+            render_lines(&lines, color)
+        }
+        Format::JsonLines => render_json_lines(&lines, Some(code)),
+    };
+    (report, code)
+}
+
+/// Same as [`format_error`], but returns the lines of the report
+/// separately, a message spanning several lines being kept whole.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::{format_lines, ColorChoice, COLOR};
+///
+/// COLOR.set(ColorChoice::Never).unwrap();
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh\nMm-ba-ba-beh"))
+///     .context("Doom-boom-ba-beh")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     format_lines(&error),
+///     ["error: Doom-boom-ba-beh", "caused by: Mm-noom-ba-deh\nMm-ba-ba-beh"]
+/// );
+/// ```
+pub fn format_lines(error: &Error) -> Vec<String> {
+    let lines = report_lines(std::slice::from_ref(error));
+    rendered_lines(&lines, output::will_use_color())
+}
+
+/// Wraps the plain report for `error` into a new error, so that it can be
+/// propagated with the rendered text baked in.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::{anyhow, Context};
+/// use eoe::to_anyhow;
+///
+/// let error = Err::<(), _>(anyhow!("Mm-noom-ba-deh"))
+///     .context("Doom-boom-ba-beh")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     to_anyhow(&error).to_string(),
+///     "error: Doom-boom-ba-beh\ncaused by: Mm-noom-ba-deh"
+/// );
+/// ```
+pub fn to_anyhow(error: &Error) -> Error {
+    let lines = report_lines(std::slice::from_ref(error));
+    Error::msg(render_lines(&lines, false))
+}
+
+fn paint(style: Style, text: &str, color: bool) -> String {
+    if color {
+        style.paint(text).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+fn label_style() -> Style {
+    match BACKGROUND.get_or_init(|| Fallback::BACKGROUND).resolve() {
+        Background::Light => Fallback::LABEL_STYLE_ON_LIGHT,
+        _ => Fallback::LABEL_STYLE_ON_DARK,
+    }
+}
+
+pub(crate) fn error_type(error: &Error) -> Option<&'static str> {
+    macro_rules! search {
+        ($cause:expr, $($ty:ty),* $(,)?) => {
+            $(
+                if $cause.is::<$ty>() {
+                    return Some(stringify!($ty));
+                }
+            )*
+        };
+    }
+
+    error.chain().find_map(|cause: &(dyn StdError + 'static)| {
+        search!(
+            cause,
+            std::io::Error,
+            std::fmt::Error,
+            std::num::ParseIntError,
+            std::num::ParseFloatError,
+            std::num::TryFromIntError,
+            std::str::ParseBoolError,
+            std::str::Utf8Error,
+            std::string::FromUtf8Error,
+            std::char::ParseCharError,
+            std::net::AddrParseError,
+            std::env::VarError,
+            std::ffi::NulError,
+            std::time::SystemTimeError,
+        );
+        None
+    })
+}
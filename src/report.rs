@@ -0,0 +1,396 @@
+use crate::config::{
+    Fallback, Format, Stream, BUG_REPORT_URL, EXIT_CODE, EXIT_CODE_FROM_DEPTH, EXIT_CODE_ON_NONE,
+    FORMAT, IO_EXIT_CODES, ON_EXIT, QUIET, REASON_FN, SHOW_EXIT_CODE, TERMINAL_RESTORE_HOOK,
+};
+use crate::metrics;
+#[cfg(feature = "otel")]
+use crate::otel;
+use crate::output::{self, exit};
+#[cfg(feature = "slog")]
+use crate::render::lines;
+use crate::render::{self, Line, Severity};
+use anyhow::{anyhow, Error};
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static HOOK_RAN: AtomicBool = AtomicBool::new(false);
+
+/// What went wrong, as extracted from a carrier type.
+pub enum Failure {
+    Error(Error),
+    Errors(Vec<Error>),
+    None,
+}
+
+impl Failure {
+    /// Calls `f` with the error representing this failure: the first one
+    /// of a collection, or an `unexpected None` error.
+    pub(crate) fn with_error<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Error) -> R,
+    {
+        match self {
+            Self::Error(error) => f(error),
+            Self::Errors(errors) => f(&errors[0]),
+            Self::None => f(&anyhow!("unexpected None")),
+        }
+    }
+
+    pub(crate) fn context<C>(self, context: C) -> Self
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        match self {
+            Self::Error(error) => Self::Error(error.context(context)),
+            Self::Errors(errors) => {
+                let context = context.to_string();
+                let errors = errors
+                    .into_iter()
+                    .map(|error| error.context(context.clone()));
+                Self::Errors(errors.collect())
+            }
+            Self::None => Self::Error(anyhow!("unexpected None").context(context)),
+        }
+    }
+}
+
+fn restore_terminal() {
+    if let Some(hook) = TERMINAL_RESTORE_HOOK.get() {
+        hook();
+    }
+}
+
+fn is_quiet() -> bool {
+    *QUIET.get_or_init(|| Fallback::QUIET)
+}
+
+fn print(failure: &Failure, code: Option<i32>) {
+    print_lines(render::lines(failure), code);
+    print_reason(failure);
+}
+
+fn push_exit_code(lines: &mut Vec<Line>, code: i32) {
+    if *SHOW_EXIT_CODE.get_or_init(|| Fallback::SHOW_EXIT_CODE) {
+        let message = format!("exiting with code {}", code);
+        lines.push(Line::new(Severity::Note, 0, message));
+    }
+}
+
+fn print_reason(failure: &Failure) {
+    if is_quiet() {
+        return;
+    }
+    if let Some(reason) = failure.with_error(reason) {
+        let format = *FORMAT.get_or_init(|| Fallback::FORMAT);
+        output::print_to(Severity::Error.stream(), &reason_line(reason, format));
+    }
+}
+
+/// Returns the [`REASON_FN`] category of `error`, if any.
+pub(crate) fn reason(error: &Error) -> Option<&'static str> {
+    REASON_FN.get().and_then(|reason_fn| reason_fn(error))
+}
+
+fn reason_line(reason: &str, format: Format) -> String {
+    match format {
+        Format::Human => format!("EOE_REASON={}\n", reason),
+        Format::JsonLines => format!(
+            "{{\"level\":\"reason\",\"msg\":{}}}\n",
+            render::json_string(reason)
+        ),
+    }
+}
+
+/// Prints `lines` in the configured [`FORMAT`], each to the stream of its
+/// severity, mentioning `code` if known.
+pub(crate) fn print_lines(lines: Vec<Line>, code: Option<i32>) {
+    restore_terminal();
+    if is_quiet() {
+        return;
+    }
+    for (stream, report) in render_report(lines, code, None, output::will_use_color_on) {
+        output::print_to(stream, &report);
+    }
+}
+
+/// Renders `lines` in the configured [`FORMAT`], followed by the notes
+/// mentioning `code` and [`BUG_REPORT_URL`] and by the `reason` line, by
+/// stream, each chunk ending with a newline.
+///
+/// Whether to paint the text for a stream is up to `color`.
+pub(crate) fn render_report<F>(
+    mut lines: Vec<Line>,
+    code: Option<i32>,
+    reason: Option<&str>,
+    color: F,
+) -> Vec<(Stream, String)>
+where
+    F: Fn(Stream) -> bool,
+{
+    let format = *FORMAT.get_or_init(|| Fallback::FORMAT);
+    if format == Format::Human {
+        if let Some(code) = code {
+            push_exit_code(&mut lines, code);
+        }
+        if let Some(&url) = BUG_REPORT_URL.get() {
+            let message = format!("please report this error at {}", url);
+            let mut line = Line::new(Severity::Note, 0, message);
+            line.message_url = Some(url.to_string());
+            lines.push(line);
+        }
+    }
+    let mut chunks = Vec::new();
+    for run in lines.chunk_by(|a, b| a.severity.stream() == b.severity.stream()) {
+        let stream = run[0].severity.stream();
+        let mut report = match format {
+            Format::Human => render::render_lines(run, color(stream)),
+            Format::JsonLines => render::render_json_lines(run, code),
+        };
+        report.push('\n');
+        chunks.push((stream, report));
+    }
+    if let Some(reason) = reason {
+        chunks.push((Severity::Error.stream(), reason_line(reason, format)));
+    }
+    chunks
+}
+
+/// Runs the [`ON_EXIT`] hook, if any, then exits with `code`.
+///
+/// The hook runs at most once, and the process exits even if it panics,
+/// with `141` if the report hit a broken pipe. It is skipped under
+/// [`DRY_RUN`](crate::DRY_RUN). With the `otel` feature, the error is
+/// first recorded as an exception event on the current span.
+fn exit_after_hook(failure: &Failure, code: i32) -> ! {
+    #[cfg(feature = "otel")]
+    failure.with_error(otel::record);
+    if let Some(hook) = ON_EXIT.get() {
+        if !output::is_dry_run() && !HOOK_RAN.swap(true, Ordering::SeqCst) {
+            let flushed = output::flush();
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| failure.with_error(hook)));
+            if flushed.is_err() {
+                output::exit_broken_pipe();
+            }
+        }
+    }
+    exit(code)
+}
+
+pub(crate) fn exit_with(failure: Failure) -> ! {
+    let code = exit_code(&failure);
+    print(&failure, Some(code));
+    exit_after_hook(&failure, code);
+}
+
+/// Same as [`exit_with`], but with `context` added to the failure first.
+///
+/// The code is resolved before, so that `None` still exits with
+/// [`EXIT_CODE_ON_NONE`](crate::EXIT_CODE_ON_NONE).
+pub(crate) fn exit_with_context<C>(failure: Failure, context: C) -> !
+where
+    C: Display + Send + Sync + 'static,
+{
+    let none_code = matches!(failure, Failure::None).then(|| exit_code(&failure));
+    let failure = failure.context(context);
+    let code = none_code.unwrap_or_else(|| exit_code(&failure));
+    exit_with_code(failure, code)
+}
+
+pub(crate) fn exit_with_code(failure: Failure, code: i32) -> ! {
+    print(&failure, Some(code));
+    exit_after_hook(&failure, code);
+}
+
+/// Same as [`exit_with`], but reports all errors of a collection.
+pub(crate) fn exit_with_all(failure: Failure) -> ! {
+    let code = exit_code(&failure);
+    let lines = match &failure {
+        Failure::Errors(errors) => render::report_lines(errors),
+        failure => render::lines(failure),
+    };
+    print_lines(lines, Some(code));
+    print_reason(&failure);
+    exit_after_hook(&failure, code);
+}
+
+pub(crate) fn exit_with_lines(lines: Vec<Line>) -> ! {
+    let code = if *EXIT_CODE_FROM_DEPTH.get_or_init(|| Fallback::EXIT_CODE_FROM_DEPTH) {
+        let causes = lines
+            .iter()
+            .filter(|line| line.severity == Severity::CausedBy)
+            .count();
+        depth_exit_code(causes)
+    } else {
+        *EXIT_CODE.get_or_init(|| Fallback::EXIT_CODE)
+    };
+    let failure = Failure::Error(Error::msg(lines[0].message.clone()));
+    print_lines(lines, Some(code));
+    exit_after_hook(&failure, code);
+}
+
+pub(crate) fn exit_grouped<F>(failure: Failure, classify: F) -> !
+where
+    F: Fn(&str) -> &'static str,
+{
+    let code = exit_code(&failure);
+    let lines = render::group(render::lines(&failure), classify);
+    print_lines(lines, Some(code));
+    print_reason(&failure);
+    exit_after_hook(&failure, code);
+}
+
+pub(crate) fn exit_with_status_file(failure: Failure, path: &Path) -> ! {
+    let code = exit_code(&failure);
+    print(&failure, Some(code));
+    if !output::is_dry_run() {
+        let status = failure.with_error(|error| render::status_json(error, code));
+        // Best effort, the report has been printed anyway.
+        let _ = fs::write(path, status + "\n");
+    }
+    exit_after_hook(&failure, code);
+}
+
+pub(crate) fn exit_with_debug(failure: Failure) -> ! {
+    let code = exit_code(&failure);
+    print_lines(render::debug_lines(&failure), Some(code));
+    print_reason(&failure);
+    exit_after_hook(&failure, code);
+}
+
+pub(crate) fn exit_with_reporting(failure: Failure) -> ! {
+    let code = exit_code(&failure);
+    print(&failure, Some(code));
+    metrics::record(code);
+    exit_after_hook(&failure, code);
+}
+
+#[cfg(feature = "slog")]
+pub(crate) fn exit_with_slog(failure: Failure, logger: &slog::Logger) -> ! {
+    restore_terminal();
+    let code = exit_code(&failure);
+    for line in lines(&failure) {
+        let message = &line.message;
+        match line.severity {
+            Severity::Error => slog::error!(logger, "{}", message; "exit_code" => code),
+            Severity::CausedBy => {
+                slog::error!(logger, "caused by: {}", message; "exit_code" => code)
+            }
+            Severity::Note => slog::info!(logger, "{}", message; "exit_code" => code),
+            Severity::Group => slog::error!(logger, "{}:", message; "exit_code" => code),
+        }
+    }
+    exit_after_hook(&failure, code);
+}
+
+pub(crate) fn exit_with_error(error: Error) -> ! {
+    exit_with(Failure::Error(error))
+}
+
+pub(crate) fn exit_with_none() -> ! {
+    exit_with(Failure::None)
+}
+
+pub(crate) fn exit_then_reexec<P>(failure: Failure, predicate: P) -> !
+where
+    P: FnOnce(&Error) -> bool,
+{
+    let restart = failure.with_error(predicate);
+    if !restart {
+        exit_with(failure);
+    }
+    print(&failure, None);
+    if output::is_simulated() {
+        exit(exit_code(&failure));
+    }
+    if output::flush().is_err() {
+        output::exit_broken_pipe();
+    }
+    reexec()
+}
+
+#[cfg(unix)]
+fn reexec() -> ! {
+    use std::os::unix::process::CommandExt;
+
+    let error = match current_command() {
+        Ok(mut command) => command.exec(),
+        Err(error) => error,
+    };
+    exit_with_error(Error::from(error).context("failed to restart"))
+}
+
+#[cfg(not(unix))]
+fn reexec() -> ! {
+    match current_command().and_then(|mut command| command.status()) {
+        Ok(status) => exit(status.code().unwrap_or(1)),
+        Err(error) => exit_with_error(Error::from(error).context("failed to restart")),
+    }
+}
+
+fn current_command() -> std::io::Result<Command> {
+    let mut command = Command::new(env::current_exe()?);
+    command.args(env::args_os().skip(1));
+    Ok(command)
+}
+
+pub(crate) fn exit_code(failure: &Failure) -> i32 {
+    match failure {
+        Failure::Error(error) => error_exit_code(error),
+        Failure::Errors(errors) => error_exit_code(&errors[0]),
+        Failure::None => {
+            let code = *EXIT_CODE.get_or_init(|| Fallback::EXIT_CODE);
+            EXIT_CODE_ON_NONE.get().copied().unwrap_or(code)
+        }
+    }
+}
+
+/// Returns the code to exit with on `error`.
+pub(crate) fn error_exit_code(error: &Error) -> i32 {
+    if *EXIT_CODE_FROM_DEPTH.get_or_init(|| Fallback::EXIT_CODE_FROM_DEPTH) {
+        return depth_exit_code(error.chain().count() - 1);
+    }
+    let code = *EXIT_CODE.get_or_init(|| Fallback::EXIT_CODE);
+    if !*IO_EXIT_CODES.get_or_init(|| Fallback::IO_EXIT_CODES) {
+        return code;
+    }
+    match error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<io::Error>())
+    {
+        Some(error) => io_exit_code(error.kind()),
+        None => code,
+    }
+}
+
+/// Derives the code from the number of causes, for
+/// [`EXIT_CODE_FROM_DEPTH`].
+fn depth_exit_code(causes: usize) -> i32 {
+    (1 + causes).min(255) as i32
+}
+
+/// Maps the kind of an I/O error to a code from `sysexits.h`.
+fn io_exit_code(kind: io::ErrorKind) -> i32 {
+    use io::ErrorKind::*;
+
+    match kind {
+        // EX_NOINPUT
+        NotFound => 66,
+        // EX_NOPERM
+        PermissionDenied => 77,
+        // EX_CANTCREAT
+        AlreadyExists => 73,
+        // EX_DATAERR
+        InvalidInput | InvalidData | UnexpectedEof => 65,
+        // EX_UNAVAILABLE
+        ConnectionRefused | ConnectionReset | ConnectionAborted | NotConnected | AddrInUse
+        | AddrNotAvailable | TimedOut => 69,
+        // EX_IOERR
+        _ => 74,
+    }
+}
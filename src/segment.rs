@@ -0,0 +1,44 @@
+use ansi_term::Style;
+use std::fmt::{self, Display, Formatter};
+
+/// A piece of text along with the style it is painted with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment<S = &'static str> {
+    pub text: S,
+    pub style: Style,
+}
+
+impl<S> Segment<S> {
+    /// Creates a segment painted with the given style.
+    pub const fn new(text: S, style: Style) -> Self {
+        Self { text, style }
+    }
+
+    /// Creates an unstyled segment.
+    pub const fn plain(text: S) -> Self {
+        Self::new(text, PLAIN)
+    }
+}
+
+impl<S> Display for Segment<S>
+where
+    S: AsRef<str>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.style.paint(self.text.as_ref()))
+    }
+}
+
+/// Same as [`Style::new`], but usable in constants.
+pub(crate) const PLAIN: Style = Style {
+    foreground: None,
+    background: None,
+    is_bold: false,
+    is_dimmed: false,
+    is_italic: false,
+    is_underline: false,
+    is_blink: false,
+    is_reverse: false,
+    is_hidden: false,
+    is_strikethrough: false,
+};
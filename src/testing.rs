@@ -0,0 +1,102 @@
+//! Helpers for testing termination paths in-process.
+//!
+//! Requires the `testing` feature.
+
+use crate::output::{self, Exit};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+/// Runs `f`, capturing what would have been printed and the code the
+/// process would have exited with, instead of terminating.
+///
+/// If `f` returns normally, the code is `0`. Panics other than the
+/// captured exit are propagated.
+///
+/// # Examples
+///
+/// ```
+/// use eoe::testing::run_capturing;
+/// use eoe::ExitOnError;
+///
+/// let (output, code) = run_capturing(|| {
+///     None::<()>.exit_on_error();
+/// });
+/// assert!(output.contains("unexpected None"));
+/// assert_eq!(code, 1);
+///
+/// let (output, code) = run_capturing(|| {
+///     Some(()).exit_on_error();
+/// });
+/// assert!(output.is_empty());
+/// assert_eq!(code, 0);
+/// ```
+pub fn run_capturing<F>(f: F) -> (String, i32)
+where
+    F: FnOnce(),
+{
+    match capture(f) {
+        (output, Ok(())) => (output, 0),
+        (output, Err(code)) => (output, code),
+    }
+}
+
+/// Runs `f`, returning its value if it terminates normally, or what would
+/// have been printed and the code the process would have exited with
+/// otherwise.
+///
+/// Panics other than the captured exit are propagated.
+///
+/// # Examples
+///
+/// The success path hands back the very value it was given, whether `Copy`
+/// or not:
+///
+/// ```
+/// use eoe::testing::catch_exit;
+/// use eoe::ExitOnError;
+///
+/// fn roundtrip<T: Clone + PartialEq + std::fmt::Debug>(value: T) {
+///     let result = catch_exit(|| Ok::<_, std::io::Error>(value.clone()).exit_on_error());
+///     assert_eq!(result.unwrap(), value);
+///     let result = catch_exit(|| Some(value.clone()).exit_on_error());
+///     assert_eq!(result.unwrap(), value);
+/// }
+///
+/// roundtrip(42);
+/// roundtrip(String::from("Mm-noom-ba-deh"));
+/// roundtrip(vec![Box::new(1), Box::new(2)]);
+///
+/// let value = Box::new(String::from("Mm-noom-ba-deh"));
+/// let address = &*value as *const String;
+/// let value = catch_exit(|| Some(value).exit_on_error()).unwrap();
+/// assert_eq!(&*value as *const String, address);
+///
+/// let (output, code) = catch_exit(|| None::<String>.exit_on_error()).unwrap_err();
+/// assert!(output.contains("unexpected None"));
+/// assert_eq!(code, 1);
+/// ```
+pub fn catch_exit<T, F>(f: F) -> Result<T, (String, i32)>
+where
+    F: FnOnce() -> T,
+{
+    match capture(f) {
+        (_, Ok(value)) => Ok(value),
+        (output, Err(code)) => Err((output, code)),
+    }
+}
+
+fn capture<T, F>(f: F) -> (String, Result<T, i32>)
+where
+    F: FnOnce() -> T,
+{
+    let previous = output::start_capture();
+    let result = catch_unwind(AssertUnwindSafe(f));
+    let buffer = output::stop_capture(previous);
+    let output = String::from_utf8_lossy(&buffer).into_owned();
+    match result {
+        Ok(value) => (output, Ok(value)),
+        Err(payload) => match payload.downcast::<Exit>() {
+            Ok(exit) => (output, Err(exit.0)),
+            Err(payload) => resume_unwind(payload),
+        },
+    }
+}
@@ -0,0 +1,106 @@
+use crate::render::{self, Line, Severity};
+use crate::report;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+/// Report assembled step by step, for errors that do not come as a
+/// single [`anyhow::Error`].
+///
+/// # Examples
+///
+/// ```should_panic
+/// use eoe::ReportWriter;
+///
+/// let mut report = ReportWriter::new("Doo-boo-boom-ba-beh-beh");
+/// report.push_cause("Doom-boom-ba-beh");
+/// report.push_cause("Mm-noom-ba-deh");
+/// report.exit();
+/// ```
+#[derive(Debug)]
+pub struct ReportWriter {
+    message: String,
+    causes: Vec<String>,
+}
+
+impl ReportWriter {
+    /// Starts a report with the top message.
+    pub fn new<M>(message: M) -> Self
+    where
+        M: Display,
+    {
+        Self {
+            message: message.to_string(),
+            causes: Vec::new(),
+        }
+    }
+
+    /// Appends a cause below the previous ones.
+    pub fn push_cause<C>(&mut self, cause: C) -> &mut Self
+    where
+        C: Display,
+    {
+        self.causes.push(cause.to_string());
+        self
+    }
+
+    /// Prints the report and exits the process.
+    ///
+    /// The code follows [`EXIT_CODE`](crate::EXIT_CODE), or with
+    /// [`EXIT_CODE_FROM_DEPTH`](crate::EXIT_CODE_FROM_DEPTH), the number
+    /// of causes pushed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eoe::testing::run_capturing;
+    /// use eoe::{ReportWriter, EXIT_CODE_FROM_DEPTH};
+    ///
+    /// EXIT_CODE_FROM_DEPTH.set(true).unwrap();
+    ///
+    /// let (_, code) = run_capturing(|| {
+    ///     let mut report = ReportWriter::new("Doo-boo-boom-ba-beh-beh");
+    ///     report.push_cause("Doom-boom-ba-beh");
+    ///     report.push_cause("Mm-noom-ba-deh");
+    ///     report.exit();
+    /// });
+    /// assert_eq!(code, 3);
+    /// ```
+    pub fn exit(self) -> ! {
+        report::exit_with_lines(self.into_lines())
+    }
+
+    /// Writes the report to `writer`, without colors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eoe::ReportWriter;
+    ///
+    /// let mut report = ReportWriter::new("Doom-boom-ba-beh");
+    /// report.push_cause("Mm-noom-ba-deh");
+    ///
+    /// let mut buffer = Vec::new();
+    /// report.finish_to(&mut buffer).unwrap();
+    /// assert_eq!(buffer, b"error: Doom-boom-ba-beh\ncaused by: Mm-noom-ba-deh\n");
+    /// ```
+    pub fn finish_to<W>(self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writeln!(
+            writer,
+            "{}",
+            render::render_lines(&self.into_lines(), false)
+        )
+    }
+
+    fn into_lines(self) -> Vec<Line> {
+        let causes = self.causes.len();
+        let mut lines = vec![Line::new(Severity::Error, 0, self.message)];
+        for (index, cause) in self.causes.into_iter().enumerate() {
+            lines.push(Line::new(Severity::CausedBy, index + 1, cause));
+        }
+        lines.iter_mut().for_each(|line| line.causes = causes);
+        lines
+    }
+}